@@ -5,8 +5,13 @@
  * LICENSE file in the root directory of this source tree.
  */
 
-use std::io::{self, Cursor};
+use std::{
+	fmt::{self, Display},
+	io::{self, Cursor},
+	time::Duration,
+};
 use byteorder::{LittleEndian, ReadBytesExt};
+use clap::ValueEnum;
 
 use kwik::file::binary::{
 	SizedChunk,
@@ -14,10 +19,11 @@ use kwik::file::binary::{
 	WriteChunk,
 };
 
-#[derive(PartialEq)]
+#[derive(Clone, PartialEq, ValueEnum)]
 pub enum Command {
 	Get,
 	Set,
+	Del,
 }
 
 pub struct Access {
@@ -27,9 +33,12 @@ pub struct Access {
 	pub key: String,
 	pub value: Box<[u8]>,
 
-	pub ttl: Option<u32>,
+	pub ttl: Option<Duration>,
 }
 
+/// A chunk is the fixed-size record of a v1 trace: the timestamp, command,
+/// numeric key, value size, and TTL in seconds. The value's bytes are not
+/// part of the chunk, so they are read back zero-filled.
 impl SizedChunk for Access {
 	fn chunk_size() -> usize {
 		25
@@ -54,7 +63,7 @@ impl ReadChunk for Access {
 
 		let ttl = match rdr.read_u32::<LittleEndian>()? {
 			0 => None,
-			ttl => Some(ttl),
+			ttl => Some(Duration::from_secs(ttl.into())),
 		};
 
 		let access = Access {
@@ -71,29 +80,50 @@ impl ReadChunk for Access {
 	}
 }
 
+/// Only accesses which read back unchanged can be written, so the key must
+/// be a number without leading zeros, the value must be zero-filled, and
+/// the TTL must be a non-zero number of whole seconds.
 impl WriteChunk for Access {
 	fn as_chunk(&self, buf: &mut Vec<u8>) -> io::Result<()> {
+		// keys such as 007 would be read back as a different string
 		let key = self.key
 			.parse::<u64>()
-			.expect("Invalid access key.");
+			.ok()
+			.filter(|key| key.to_string() == self.key)
+			.ok_or_else(|| invalid_chunk("Access key is not numeric."))?;
+
+		let size = u32::try_from(self.value.len())
+			.map_err(|_| invalid_chunk("Access value is too large."))?;
+
+		if self.value.iter().any(|byte| *byte != 0) {
+			return Err(invalid_chunk("Access value is not zero-filled."));
+		}
 
-		let size = self.value.len() as u32;
+		let ttl = match self.ttl {
+			None => 0,
+
+			Some(ttl) => match u32::try_from(ttl.as_secs()) {
+				Ok(secs) if secs > 0 && ttl.subsec_nanos() == 0 => secs,
+				_ => return Err(invalid_chunk("Access TTL is not a whole number of seconds.")),
+			},
+		};
 
 		buf.extend_from_slice(&self.timestamp.to_le_bytes());
 		buf.extend_from_slice(&self.command.as_byte().to_le_bytes());
 		buf.extend_from_slice(&key.to_le_bytes());
 		buf.extend_from_slice(&size.to_le_bytes());
-		buf.extend_from_slice(&self.ttl.unwrap_or(0).to_le_bytes());
+		buf.extend_from_slice(&ttl.to_le_bytes());
 
 		Ok(())
 	}
 }
 
 impl Command {
-	fn from_byte(byte: u8) -> io::Result<Self> {
+	pub fn from_byte(byte: u8) -> io::Result<Self> {
 		match byte {
 			0 => Ok(Command::Get),
 			1 => Ok(Command::Set),
+			2 => Ok(Command::Del),
 
 			_ => Err(io::Error::new(
 				io::ErrorKind::InvalidData,
@@ -106,6 +136,139 @@ impl Command {
 		match self {
 			Command::Get => 0,
 			Command::Set => 1,
+			Command::Del => 2,
+		}
+	}
+}
+
+impl Display for Command {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		let s = match self {
+			Command::Get => "GET",
+			Command::Set => "SET",
+			Command::Del => "DEL",
+		};
+
+		write!(f, "{s}")
+	}
+}
+
+fn invalid_chunk(message: &'static str) -> io::Error {
+	io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+#[cfg(test)]
+mod tests {
+	use std::{io, time::Duration};
+	use rand::{Rng, SeedableRng, rngs::StdRng};
+	use kwik::file::binary::{SizedChunk, ReadChunk, WriteChunk};
+	use crate::access::{Access, Command};
+
+	#[test]
+	fn chunk_round_trips_valid_accesses() {
+		let mut rng = StdRng::seed_from_u64(0);
+
+		for _ in 0..10_000 {
+			let command = match rng.random_range(0..3) {
+				0 => Command::Get,
+				1 => Command::Set,
+				_ => Command::Del,
+			};
+
+			let ttl = rng
+				.random_bool(0.5)
+				.then(|| Duration::from_secs(rng.random_range(1..=u32::MAX).into()));
+
+			let access = Access {
+				timestamp: rng.random(),
+				command,
+
+				key: rng.random::<u64>().to_string(),
+				value: vec![0; rng.random_range(0..4096)].into(),
+
+				ttl,
+			};
+
+			let mut buf = Vec::new();
+			access.as_chunk(&mut buf).expect("Could not write access.");
+
+			assert_eq!(buf.len(), Access::chunk_size());
+
+			let read_access = Access::from_chunk(&buf).expect("Could not read access.");
+
+			assert_eq!(read_access.timestamp, access.timestamp);
+			assert!(read_access.command == access.command);
+			assert_eq!(read_access.key, access.key);
+			assert_eq!(read_access.value, access.value);
+			assert_eq!(read_access.ttl, access.ttl);
 		}
 	}
+
+	#[test]
+	fn chunk_rejects_non_numeric_key() {
+		let mut access = get_access();
+		access.key = "key".to_owned();
+
+		assert_invalid(&access);
+	}
+
+	#[test]
+	fn chunk_rejects_non_canonical_key() {
+		let mut access = get_access();
+		access.key = "007".to_owned();
+
+		assert_invalid(&access);
+	}
+
+	#[test]
+	fn chunk_rejects_non_zero_value() {
+		let mut access = get_access();
+		access.value = vec![0, 1, 0].into();
+
+		assert_invalid(&access);
+	}
+
+	#[test]
+	fn chunk_rejects_sub_second_ttl() {
+		let mut access = get_access();
+		access.ttl = Some(Duration::from_millis(1500));
+
+		assert_invalid(&access);
+	}
+
+	#[test]
+	fn chunk_rejects_zero_ttl() {
+		let mut access = get_access();
+		access.ttl = Some(Duration::ZERO);
+
+		assert_invalid(&access);
+	}
+
+	#[test]
+	fn chunk_rejects_ttl_over_u32() {
+		let mut access = get_access();
+		access.ttl = Some(Duration::from_secs(u64::from(u32::MAX) + 1));
+
+		assert_invalid(&access);
+	}
+
+	fn get_access() -> Access {
+		Access {
+			timestamp: 1,
+			command: Command::Set,
+
+			key: "7".to_owned(),
+			value: vec![0; 16].into(),
+
+			ttl: Some(Duration::from_secs(60)),
+		}
+	}
+
+	fn assert_invalid(access: &Access) {
+		let err = access
+			.as_chunk(&mut Vec::new())
+			.expect_err("Invalid access was written.");
+
+		assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+	}
 }
@@ -6,60 +6,216 @@
  */
 
 use std::{
+	mem,
+	thread,
+	collections::{HashMap, HashSet, VecDeque},
+	hash::{Hash, Hasher, DefaultHasher},
+	sync::{
+		Arc,
+		Once,
+		atomic::{AtomicBool, Ordering},
+	},
 	fmt::{self, Display},
 	time::{Instant, Duration},
 };
 
 use clap::ValueEnum;
-use crossbeam_channel::Receiver;
+use crossbeam_channel::{Receiver, RecvTimeoutError};
+use tracing::{debug, warn, error};
+use rand::{RngCore, SeedableRng, rngs::StdRng};
 use paper_client::{PaperClient, PaperClientError};
 
 use crate::{
 	access::{Access, Command},
-	stats::Stats,
+	stats::{Stats, StatsMemory, ErrorKind},
+	metrics::LiveMetrics,
+	raw::RawOutput,
+	timer::{Timer, TimerInstant, StdTimer},
+	warmup::WarmupMonitor,
 };
 
-pub type ClientReceiver = Receiver<ClientEvent>;
+/// Events are sent along with the instant they were enqueued.
+pub type ClientReceiver = Receiver<(ClientEvent, Instant)>;
+
+const RECONNECT_BASE_BACKOFF: Duration = Duration::from_millis(100);
+
+// rounded TTLs are only warned about once across every client
+static TTL_ROUNDING_WARNING: Once = Once::new();
 
 pub struct BenchmarkClient {
-	client: PaperClient,
+	// requests are issued over each connection in turn
+	connections: Vec<PaperClient>,
+	connection_index: usize,
+
+	paper_addr: String,
+	auth: Option<String>,
+
 	events: ClientReceiver,
 	stats: Stats,
 
+	// once the producer has sent every event, the requests which drain
+	// the remaining events are recorded into separate stats
+	is_draining: Arc<AtomicBool>,
+	steady_stats: Option<Stats>,
+
 	client_type: ClientType,
+	backing_store_latency: Duration,
+
+	error_count: u64,
+	max_errors: u64,
+	error_policy: ErrorPolicy,
+
+	max_reconnect_attempts: u32,
+
+	value_size: Option<u32>,
+	rng: StdRng,
+
+	// the hash of the last value SET to each key, kept only when values
+	// are verified
+	value_hashes: Option<HashMap<String, u64>>,
+
+	// counts the value hashes towards the stats memory estimate
+	stats_memory: Option<Arc<StatsMemory>>,
+
+	idle_timeout: Option<Duration>,
+	populate_ttl: Option<Duration>,
+	key_prefix: Option<String>,
+
+	connect_latencies: Vec<Duration>,
+	auth_latencies: Vec<Duration>,
+
+	load_model: LoadModel,
+
+	write_back_batch_size: usize,
+	write_back_interval: Duration,
+	write_back_buffer: VecDeque<Access>,
+
+	// when the oldest SET in the buffer was buffered
+	buffered_since: Option<Instant>,
+
+	// SETs buffered during the warmup are flushed without being recorded
+	has_warmup_sets: bool,
+
+	timer: Arc<dyn Timer>,
+	warmup_monitor: Option<Arc<WarmupMonitor>>,
 }
 
-#[derive(Debug, Copy, Clone, ValueEnum)]
+#[derive(Debug, Copy, Clone, PartialEq, ValueEnum)]
 pub enum ClientType {
 	Lookaside,
 	ReadThrough,
+	WriteThrough,
+	WriteBack,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, ValueEnum)]
+pub enum LoadModel {
+	/// Events are offered at their scheduled time whether or not the
+	/// clients keep up, so requests may queue.
+	Open,
+
+	/// Events are offered only as fast as the clients complete them.
+	Closed,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum ErrorPolicy {
+	/// The client aborts once it exceeds the maximum number of errors.
+	Abort,
+
+	/// Every error is logged and counted, and the client keeps going.
+	Continue,
 }
 
 pub enum ClientEvent {
 	Ping,
-	Access(Access),
+
+	/// An access along with the instant it was scheduled to be sent, if
+	/// latencies should be measured from the schedule rather than from
+	/// when the request started.
+	Access(Access, Option<Instant>),
+
+	WarmupPing,
+	Warmup(Access),
 }
 
 impl BenchmarkClient {
+	/// Opens `connections` connections to the cache, over which the client
+	/// issues its requests in turn.
 	pub fn new(
 		paper_addr: &str,
 		auth: Option<String>,
 		events: ClientReceiver,
+		connections: usize,
 	) -> Result<Self, PaperClientError> {
-		let mut client = PaperClient::new(paper_addr)?;
+		assert!(connections > 0, "A client needs at least one connection.");
 
-		if let Some(token) = &auth {
-			client.auth(token)?;
-		}
+		let mut clients = Vec::with_capacity(connections);
+		let mut connect_latencies = Vec::with_capacity(connections);
+		let mut auth_latencies = Vec::new();
 
-		client.wipe()?;
+		for _ in 0..connections {
+			let connect_start_time = Instant::now();
+			let mut client = PaperClient::new(paper_addr)?;
+			let connect_latency = connect_start_time.elapsed();
+
+			if let Some(token) = &auth {
+				let auth_start_time = Instant::now();
+				client.auth(token)?;
+				auth_latencies.push(auth_start_time.elapsed());
+			}
+
+			debug!(addr = paper_addr, connect_us = connect_latency.as_micros() as u64, "Connected to cache");
+
+			clients.push(client);
+			connect_latencies.push(connect_latency);
+		}
 
 		let benchmark_client = BenchmarkClient {
-			client,
+			connections: clients,
+			connection_index: 0,
+
+			paper_addr: paper_addr.to_owned(),
+			auth,
 			events,
 			stats: Stats::default(),
 
+			is_draining: Arc::new(AtomicBool::new(false)),
+			steady_stats: None,
+
 			client_type: ClientType::Lookaside,
+			backing_store_latency: Duration::ZERO,
+
+			error_count: 0,
+			max_errors: 0,
+			error_policy: ErrorPolicy::Abort,
+
+			max_reconnect_attempts: 0,
+
+			value_size: None,
+			rng: StdRng::from_os_rng(),
+
+			value_hashes: None,
+
+			stats_memory: None,
+
+			idle_timeout: None,
+			populate_ttl: None,
+			key_prefix: None,
+
+			connect_latencies,
+			auth_latencies,
+
+			load_model: LoadModel::Closed,
+
+			write_back_batch_size: 1,
+			write_back_interval: Duration::MAX,
+			write_back_buffer: VecDeque::new(),
+			buffered_since: None,
+			has_warmup_sets: false,
+
+			timer: Arc::new(StdTimer),
+			warmup_monitor: None,
 		};
 
 		Ok(benchmark_client)
@@ -70,49 +226,456 @@ impl BenchmarkClient {
 		self
 	}
 
-	pub fn run(&mut self) -> Result<Stats, PaperClientError> {
-		let max_wait = Duration::from_secs(5);
+	pub fn with_backing_store_latency(mut self, latency: Duration) -> Self {
+		self.backing_store_latency = latency;
+		self
+	}
+
+	pub fn with_max_errors(mut self, max_errors: u64) -> Self {
+		self.max_errors = max_errors;
+		self
+	}
+
+	pub fn with_error_policy(mut self, error_policy: ErrorPolicy) -> Self {
+		self.error_policy = error_policy;
+		self
+	}
+
+	pub fn with_max_reconnect_attempts(mut self, max_reconnect_attempts: u32) -> Self {
+		self.max_reconnect_attempts = max_reconnect_attempts;
+		self
+	}
+
+	pub fn with_value_size(mut self, value_size: Option<u32>) -> Self {
+		self.value_size = value_size;
+		self
+	}
+
+	/// Sets how long the client waits for an event before it stops. By
+	/// default, the client runs until all events have been sent.
+	pub fn with_idle_timeout(mut self, idle_timeout: Option<Duration>) -> Self {
+		self.idle_timeout = idle_timeout;
+		self
+	}
+
+	/// Sets the TTL of the objects populated into the cache after a
+	/// read-through miss, overriding the trace's TTL.
+	pub fn with_populate_ttl(mut self, populate_ttl: Option<Duration>) -> Self {
+		self.populate_ttl = populate_ttl;
+		self
+	}
+
+	/// Sets a string which is prepended to every key, so that concurrent
+	/// benchmarks sharing a cache do not collide.
+	pub fn with_key_prefix(mut self, key_prefix: Option<String>) -> Self {
+		self.key_prefix = key_prefix;
+		self
+	}
+
+	/// Sets the fraction of requests whose latencies are stored.
+	pub fn with_sample_rate(mut self, sample_rate: Option<f64>) -> Self {
+		self.stats = mem::take(&mut self.stats).with_sample_rate(sample_rate);
+		self
+	}
+
+	/// Checks that the value of every GET hit matches the value this client
+	/// last SET to the key. Only lookaside clients verify values.
+	pub fn with_verify_values(mut self, verify_values: bool) -> Self {
+		self.value_hashes = verify_values.then(HashMap::new);
+		self
+	}
+
+	/// Seeds the random number generator so that the client generates the
+	/// same sequence of values across runs, though which accesses they are
+	/// sent with depends on the order in which the client receives them.
+	pub fn with_seed(mut self, seed: Option<u64>) -> Self {
+		if let Some(seed) = seed {
+			self.rng = StdRng::seed_from_u64(seed);
+		}
+
+		self
+	}
+
+	pub fn with_live_metrics(mut self, live_metrics: Option<Arc<LiveMetrics>>) -> Self {
+		self.stats = mem::take(&mut self.stats).with_live_metrics(live_metrics);
+		self
+	}
+
+	pub fn with_streaming_percentiles(mut self, streaming_percentiles: bool) -> Self {
+		self.stats = mem::take(&mut self.stats).with_streaming_percentiles(streaming_percentiles);
+		self
+	}
+
+	pub fn with_stats_memory(mut self, stats_memory: Option<Arc<StatsMemory>>) -> Self {
+		self.stats = mem::take(&mut self.stats).with_stats_memory(stats_memory.clone());
+		self.stats_memory = stats_memory;
+		self
+	}
+
+	pub fn with_raw_output(mut self, raw_output: Option<RawOutput>) -> Self {
+		self.stats = mem::take(&mut self.stats).with_raw_output(raw_output);
+		self
+	}
+
+	pub fn with_timer(mut self, timer: Arc<dyn Timer>) -> Self {
+		self.stats = mem::take(&mut self.stats).with_timer(Some(timer.clone()));
+		self.timer = timer;
+		self
+	}
+
+	pub fn with_slow_threshold(mut self, slow_threshold: Option<Duration>) -> Self {
+		self.stats = mem::take(&mut self.stats).with_slow_threshold(slow_threshold);
+		self
+	}
+
+	/// Sets the flag raised once the producer has sent every event, after
+	/// which requests are recorded into the client's drain stats.
+	pub fn with_is_draining(mut self, is_draining: Arc<AtomicBool>) -> Self {
+		self.is_draining = is_draining;
+		self
+	}
+
+	/// Reports the latency of every warmup event to the supplied monitor.
+	pub fn with_warmup_monitor(mut self, warmup_monitor: Option<Arc<WarmupMonitor>>) -> Self {
+		self.warmup_monitor = warmup_monitor;
+		self
+	}
+
+	pub fn with_hot_keys(mut self, hot_keys: Option<Arc<HashSet<String>>>) -> Self {
+		self.stats = mem::take(&mut self.stats).with_hot_keys(hot_keys);
+		self
+	}
+
+	/// Sets the load model. Under an open load model, the time each event
+	/// spends queued before its request starts is recorded.
+	pub fn with_load_model(mut self, load_model: LoadModel) -> Self {
+		self.load_model = load_model;
+		self
+	}
+
+	/// Sets when a write-back client flushes its buffered SETs: once the
+	/// buffer holds `batch_size` SETs or `interval` has passed since the
+	/// oldest of them was buffered, whichever comes first.
+	pub fn with_write_back(mut self, batch_size: usize, interval: Duration) -> Self {
+		self.write_back_batch_size = batch_size;
+		self.write_back_interval = interval;
+		self
+	}
+
+	/// Returns the time taken to open each of the client's initial
+	/// connections.
+	pub fn connect_latencies(&self) -> &[Duration] {
+		&self.connect_latencies
+	}
+
+	/// Returns the time taken to authenticate each of the client's initial
+	/// connections, which is empty if no auth token was supplied.
+	pub fn auth_latencies(&self) -> &[Duration] {
+		&self.auth_latencies
+	}
 
-		while let Ok(event) = self.events.recv_timeout(max_wait) {
-			match event {
-				ClientEvent::Ping => self.handle_ping()?,
-				ClientEvent::Access(access) => self.handle_access(access)?,
+	/// Handles events until the channel is closed or the client is idle for
+	/// too long. Returns the client's stats and the stats of the requests it
+	/// made while draining, along with the instants at which it started and
+	/// stopped handling events.
+	///
+	/// A client which continues on errors stops early with the stats it has
+	/// collected if its connection cannot be restored.
+	pub fn run(&mut self) -> Result<(Stats, Stats, Instant, Instant), PaperClientError> {
+		let start = Instant::now();
+
+		if let Err(err) = self.handle_events() {
+			match self.error_policy {
+				ErrorPolicy::Abort => return Err(err),
+				ErrorPolicy::Continue => error!(%err, "Client stopped early"),
 			}
 		}
 
-		Ok(self.stats.clone())
+		// the returned stats do not keep the raw writer open
+		let stats = mem::take(&mut self.stats).with_raw_output(None);
+
+		let (stats, drain_stats) = match self.steady_stats.take() {
+			Some(steady_stats) => (steady_stats.with_raw_output(None), stats),
+			None => (stats, Stats::default()),
+		};
+
+		Ok((stats, drain_stats, start, Instant::now()))
+	}
+
+	fn handle_events(&mut self) -> Result<(), PaperClientError> {
+		while let Some((event, enqueued)) = self.next_event()? {
+			let is_warmup = matches!(event, ClientEvent::WarmupPing | ClientEvent::Warmup(_));
+
+			if self.steady_stats.is_none() && self.is_draining.load(Ordering::Relaxed) {
+				let drain_stats = self.stats.empty_like();
+				self.steady_stats = Some(mem::replace(&mut self.stats, drain_stats));
+			}
+
+			if !is_warmup && self.has_warmup_sets {
+				self.flush_write_back()?;
+			}
+
+			if self.load_model == LoadModel::Open && !is_warmup {
+				self.stats.store_queue_time(enqueued);
+			}
+
+			self.handle_event(event)?;
+
+			if self.should_flush() {
+				self.flush_write_back()?;
+			}
+		}
+
+		// any SETs still buffered by a write-back client are flushed once
+		// there are no more events
+		self.flush_write_back()
+	}
+
+	/// Waits for the next event, returning `None` once the channel is closed
+	/// or the client has been idle for too long. Buffered SETs are flushed
+	/// if their interval passes while the client waits.
+	fn next_event(&mut self) -> Result<Option<(ClientEvent, Instant)>, PaperClientError> {
+		let idle_deadline = self.idle_timeout
+			.and_then(|idle_timeout| Instant::now().checked_add(idle_timeout));
+
+		loop {
+			let flush_deadline = self.buffered_since
+				.and_then(|buffered_since| buffered_since.checked_add(self.write_back_interval));
+
+			let deadline = match (idle_deadline, flush_deadline) {
+				(Some(idle_deadline), Some(flush_deadline)) => Some(idle_deadline.min(flush_deadline)),
+				(idle_deadline, flush_deadline) => idle_deadline.or(flush_deadline),
+			};
+
+			let result = match deadline {
+				Some(deadline) => self.events.recv_deadline(deadline),
+				None => self.events.recv().map_err(|_| RecvTimeoutError::Disconnected),
+			};
+
+			match result {
+				Ok(event) => return Ok(Some(event)),
+
+				Err(RecvTimeoutError::Timeout)
+					if flush_deadline.is_some_and(|flush_deadline| Instant::now() >= flush_deadline) =>
+				{
+					self.flush_write_back()?;
+				},
+
+				Err(_) => return Ok(None),
+			}
+		}
+	}
+
+	fn handle_event(&mut self, event: ClientEvent) -> Result<(), PaperClientError> {
+		// every request made for an event goes over the same connection
+		self.connection_index = (self.connection_index + 1) % self.connections.len();
+		self.dispatch_event(event)
+	}
+
+	/// Handles an event over the current connection. Warmup events are
+	/// handled as the event they wrap without moving to the next connection,
+	/// so warmup uses the connections in the same order as measured events.
+	fn dispatch_event(&mut self, event: ClientEvent) -> Result<(), PaperClientError> {
+		match event {
+			ClientEvent::Ping => match self.handle_ping() {
+				Err(err) => self.handle_error(err, Stats::store_ping_error),
+				result => result,
+			},
+
+			ClientEvent::Access(access, intended) => {
+				let store_error: fn(&mut Stats) = match access.command {
+					Command::Get => Stats::store_get_error,
+					Command::Set => Stats::store_set_error,
+					Command::Del => Stats::store_del_error,
+				};
+
+				match self.handle_access(access, intended) {
+					Err(err) => self.handle_error(err, store_error),
+					result => result,
+				}
+			},
+
+			ClientEvent::WarmupPing => self.handle_warmup(|client| {
+				client.dispatch_event(ClientEvent::Ping)
+			}),
+
+			ClientEvent::Warmup(access) => self.handle_warmup(|client| {
+				client.dispatch_event(ClientEvent::Access(access, None))
+			}),
+		}
+	}
+
+	/// Records a failed request and skips it, unless the client aborts on
+	/// errors and has exceeded the maximum number of errors. If the
+	/// connection was lost, the client then reconnects.
+	fn handle_error(
+		&mut self,
+		err: PaperClientError,
+		store_error: fn(&mut Stats),
+	) -> Result<(), PaperClientError> {
+		self.stats.store_error_kind(get_error_kind(&err));
+		store_error(&mut self.stats);
+		self.error_count += 1;
+
+		match self.error_policy {
+			ErrorPolicy::Abort if self.error_count > self.max_errors => {
+				error!(errors = self.error_count, %err, "Aborting after too many failed requests");
+				return Err(err);
+			},
+
+			ErrorPolicy::Abort => debug!(errors = self.error_count, %err, "Request failed"),
+			ErrorPolicy::Continue => warn!(%err, "Request failed"),
+		}
+
+		if is_connection_error(&err) && self.max_reconnect_attempts > 0 {
+			return self.reconnect();
+		}
+
+		Ok(())
+	}
+
+	fn handle_warmup<F>(&mut self, handler: F) -> Result<(), PaperClientError>
+	where
+		F: FnOnce(&mut Self) -> Result<(), PaperClientError>,
+	{
+		let start_time = self.timer.now();
+		let result = self.with_scratch_stats(handler);
+
+		// the buffer only holds warmup SETs until the first measured event
+		self.has_warmup_sets = !self.write_back_buffer.is_empty();
+
+		if let Some(warmup_monitor) = &self.warmup_monitor {
+			warmup_monitor.store_latency(self.timer.elapsed(start_time));
+		}
+
+		result
+	}
+
+	/// Records the handler's requests into a scratch set of stats which is
+	/// then discarded, so they do not affect the reported latencies.
+	fn with_scratch_stats<F>(&mut self, handler: F) -> Result<(), PaperClientError>
+	where
+		F: FnOnce(&mut Self) -> Result<(), PaperClientError>,
+	{
+		let stats = mem::take(&mut self.stats);
+		let result = handler(self);
+		self.stats = stats;
+
+		result
+	}
+
+	fn reconnect(&mut self) -> Result<(), PaperClientError> {
+		let mut backoff = RECONNECT_BASE_BACKOFF;
+		let mut attempts = 0;
+
+		loop {
+			attempts += 1;
+
+			match self.connect() {
+				Ok(client) => {
+					self.connections[self.connection_index] = client;
+					self.stats.store_reconnect();
+
+					warn!(attempts, "Reconnected to cache");
+
+					return Ok(());
+				},
+
+				Err(err) if attempts >= self.max_reconnect_attempts => {
+					error!(attempts, %err, "Could not reconnect to cache");
+					return Err(err);
+				},
+
+				Err(err) => debug!(attempts, %err, "Reconnect attempt failed"),
+			}
+
+			thread::sleep(backoff);
+			backoff *= 2;
+		}
+	}
+
+	/// Returns when a request starts, which is when it was scheduled if
+	/// latencies are measured from the schedule.
+	fn start_time(&self, intended: Option<Instant>) -> TimerInstant {
+		intended.map_or_else(|| self.timer.now(), TimerInstant::from)
+	}
+
+	fn client(&mut self) -> &mut PaperClient {
+		&mut self.connections[self.connection_index]
+	}
+
+	fn connect(&self) -> Result<PaperClient, PaperClientError> {
+		let mut client = PaperClient::new(&self.paper_addr)?;
+
+		if let Some(token) = &self.auth {
+			client.auth(token)?;
+		}
+
+		Ok(client)
 	}
 
 	fn handle_ping(&mut self) -> Result<(), PaperClientError> {
-		let start_time = Instant::now();
+		let start_time = self.timer.now();
 
-		self.client.ping()?;
+		self.client().ping()?;
 		self.stats.store_ping_time(start_time);
 
 		Ok(())
 	}
 
-	fn handle_access(&mut self, access: Access) -> Result<(), PaperClientError> {
+	fn handle_access(&mut self, mut access: Access, intended: Option<Instant>) -> Result<(), PaperClientError> {
+		// read-through clients only send the values of their backfills,
+		// which are filled on a miss
+		if access.command == Command::Set && self.client_type != ClientType::ReadThrough {
+			self.fill_value(&mut access);
+		}
+
+		if let Some(key_prefix) = &self.key_prefix {
+			access.key.insert_str(0, key_prefix);
+		}
+
+		self.stats.store_access(&access.command);
+
 		match self.client_type {
-			ClientType::Lookaside => self.handle_lookaside(access),
-			ClientType::ReadThrough => self.handle_read_through(access),
+			ClientType::Lookaside => self.handle_lookaside(access, intended),
+			ClientType::ReadThrough => self.handle_read_through(access, intended),
+			ClientType::WriteThrough => self.handle_write_through(access, intended),
+			ClientType::WriteBack => self.handle_write_back(access, intended),
 		}
 	}
 
-	fn handle_lookaside(&mut self, access: Access) -> Result<(), PaperClientError> {
+	/// Replaces the value of the access with one of the configured size, if
+	/// there is one.
+	fn fill_value(&mut self, access: &mut Access) {
+		if let Some(value_size) = self.value_size {
+			// the value is randomized rather than zero-filled so that it
+			// cannot be compressed by the server
+			let mut value = vec![0u8; value_size as usize];
+			self.rng.fill_bytes(&mut value);
+
+			access.value = value.into();
+		}
+	}
+
+	fn handle_lookaside(&mut self, access: Access, intended: Option<Instant>) -> Result<(), PaperClientError> {
 		match access.command {
 			Command::Get => {
-				let start_time = Instant::now();
+				let start_time = self.start_time(intended);
 
-				match self.client.get(&access.key) {
+				match self.client().get(&access.key) {
 					Ok(value) => {
-						self.stats.store_get_time(start_time);
-
-						let value: &str = (&value)
-							.try_into()
-							.map_err(|_| PaperClientError::Internal)?;
+						self.stats.store_get_time(start_time, &access.key);
+						self.stats.store_get_hit();
 
+						let value: &[u8] = (&value).into();
 						self.stats.store_get_size(value.len() as u64);
+						self.stats.store_raw(&access.command, start_time, value.len() as u64, Some(true));
+
+						if let Some(value_hashes) = &self.value_hashes
+							&& let Some(expected_hash) = value_hashes.get(&access.key)
+						{
+							self.stats.store_value_check(hash_value(value) == *expected_hash);
+						}
 					},
 
 					Err(err) if !matches!(err, PaperClientError::CacheError(_)) => {
@@ -120,41 +683,84 @@ impl BenchmarkClient {
 					},
 
 					Err(_) => {
-						self.stats.store_get_time(start_time);
+						self.stats.store_get_time(start_time, &access.key);
+						self.stats.store_get_miss();
+						self.stats.store_raw(&access.command, start_time, 0, Some(false));
 					},
 				}
 			},
 
 			Command::Set => {
 				let size = access.value.len() as u64;
-				let start_time = Instant::now();
+				let ttl = access.ttl;
+
+				let value_hash = self.value_hashes
+					.is_some()
+					.then(|| (access.key.clone(), hash_value(&access.value)));
 
-				self.client.set(access.key, access.value, access.ttl)?;
+				let start_time = self.start_time(intended);
+
+				self.client().set(access.key, access.value, get_ttl_secs(ttl))?;
 
 				self.stats.store_set_time(start_time);
+				self.stats.store_set_ttl(ttl);
 				self.stats.store_set_size(size);
+				self.stats.store_raw(&access.command, start_time, size, None);
+
+				if let Some(value_hashes) = &mut self.value_hashes
+					&& let Some((key, value_hash)) = value_hash
+				{
+					if let Some(stats_memory) = &self.stats_memory
+						&& !value_hashes.contains_key(&key)
+					{
+						stats_memory.store_key(&key, mem::size_of::<u64>());
+					}
+
+					value_hashes.insert(key, value_hash);
+				}
+			},
+
+			Command::Del => {
+				let start_time = self.start_time(intended);
+
+				match self.client().del(&access.key) {
+					Err(err) if !matches!(err, PaperClientError::CacheError(_)) => {
+						return Err(err);
+					},
+
+					_ => {
+						self.stats.store_del_time(start_time);
+						self.stats.store_raw(&access.command, start_time, 0, None);
+					},
+				}
+
+				if let Some(value_hashes) = &mut self.value_hashes
+					&& value_hashes.remove(&access.key).is_some()
+					&& let Some(stats_memory) = &self.stats_memory
+				{
+					stats_memory.release_key(&access.key, mem::size_of::<u64>());
+				}
 			},
 		}
 
 		Ok(())
 	}
 
-	fn handle_read_through(&mut self, access: Access) -> Result<(), PaperClientError> {
+	fn handle_read_through(&mut self, mut access: Access, intended: Option<Instant>) -> Result<(), PaperClientError> {
 		if access.command != Command::Get {
 			return Ok(());
 		}
 
-		let get_start_time = Instant::now();
+		let get_start_time = self.start_time(intended);
 
-		match self.client.get(&access.key) {
+		match self.client().get(&access.key) {
 			Ok(value) => {
-				self.stats.store_get_time(get_start_time);
-
-				let value: &str = (&value)
-					.try_into()
-					.map_err(|_| PaperClientError::Internal)?;
+				self.stats.store_get_time(get_start_time, &access.key);
+				self.stats.store_get_hit();
 
+				let value: &[u8] = (&value).into();
 				self.stats.store_get_size(value.len() as u64);
+				self.stats.store_raw(&Command::Get, get_start_time, value.len() as u64, Some(true));
 			},
 
 			Err(err) if !matches!(err, PaperClientError::CacheError(_)) => {
@@ -162,18 +768,188 @@ impl BenchmarkClient {
 			},
 
 			Err(_) => {
+				self.stats.store_get_miss();
+				self.stats.store_backfill();
+				self.stats.store_raw(&Command::Get, get_start_time, 0, Some(false));
+
+				self.fill_value(&mut access);
 				let size = access.value.len() as u64;
-				let set_start_time = Instant::now();
+				let set_start_time = self.timer.now();
 
-				self.client.set(access.key, access.value, access.ttl)?;
+				let ttl = self.populate_ttl.or(access.ttl);
+				self.client().set(access.key, access.value, get_ttl_secs(ttl))?;
 
 				self.stats.store_set_time(set_start_time);
+				self.stats.store_set_ttl(ttl);
 				self.stats.store_set_size(size);
+				self.stats.store_raw(&Command::Set, set_start_time, size, None);
 			},
 		}
 
 		Ok(())
 	}
+
+	fn handle_write_through(&mut self, access: Access, intended: Option<Instant>) -> Result<(), PaperClientError> {
+		if access.command != Command::Set {
+			return self.handle_lookaside(access, intended);
+		}
+
+		let size = access.value.len() as u64;
+		let ttl = access.ttl;
+
+		let start_time = self.start_time(intended);
+
+		self.client().set(access.key, access.value, get_ttl_secs(ttl))?;
+
+		// the write to the backing store is simulated and happens
+		// synchronously with the cache write
+		spin_sleep::sleep(self.backing_store_latency);
+
+		self.stats.store_set_time(start_time);
+		self.stats.store_set_ttl(ttl);
+		self.stats.store_set_size(size);
+		self.stats.store_raw(&Command::Set, start_time, size, None);
+
+		Ok(())
+	}
+
+	/// Buffers SETs rather than sending them to the cache immediately. The
+	/// buffer is flushed between events and while the client is idle.
+	fn handle_write_back(&mut self, access: Access, intended: Option<Instant>) -> Result<(), PaperClientError> {
+		if access.command != Command::Set {
+			return self.handle_lookaside(access, intended);
+		}
+
+		self.stats.store_buffered_set();
+
+		if self.write_back_buffer.is_empty() {
+			self.buffered_since = Some(Instant::now());
+		}
+
+		self.write_back_buffer.push_back(access);
+
+		Ok(())
+	}
+
+	/// Returns true once the write-back buffer is full or its oldest SET has
+	/// waited for the flush interval.
+	fn should_flush(&self) -> bool {
+		self.write_back_buffer.len() >= self.write_back_batch_size
+			|| self.buffered_since.is_some_and(|buffered_since| buffered_since.elapsed() >= self.write_back_interval)
+	}
+
+	/// Sends every buffered SET to the cache. A failed SET is recorded and
+	/// the rest are still sent, unless the client stops on the error, in
+	/// which case the unsent SETs stay buffered.
+	fn flush_write_back(&mut self) -> Result<(), PaperClientError> {
+		if self.write_back_buffer.is_empty() {
+			return Ok(());
+		}
+
+		if mem::take(&mut self.has_warmup_sets) {
+			let result = self.with_scratch_stats(Self::flush_write_back);
+			self.has_warmup_sets = !self.write_back_buffer.is_empty();
+
+			return result;
+		}
+
+		let start_time = self.timer.now();
+		let mut count = 0;
+
+		while let Some(access) = self.write_back_buffer.pop_front() {
+			let size = access.value.len() as u64;
+			let ttl = access.ttl;
+			let set_start_time = self.timer.now();
+
+			match self.client().set(access.key, access.value, get_ttl_secs(ttl)) {
+				Ok(_) => {
+					self.stats.store_set_ttl(ttl);
+					self.stats.store_raw(&Command::Set, set_start_time, size, None);
+					count += 1;
+				},
+
+				Err(err) => self.handle_error(err, Stats::store_set_error)?,
+			}
+		}
+
+		self.buffered_since = None;
+
+		self.stats.store_flush_time(start_time);
+		self.stats.store_flushed_sets(count);
+
+		Ok(())
+	}
+}
+
+/// Converts a TTL to the whole seconds expected by the server, rounding up
+/// so that sub-second TTLs do not become unlimited. The first TTL which is
+/// rounded is warned about.
+fn get_ttl_secs(ttl: Option<Duration>) -> Option<u32> {
+	ttl.map(|ttl| {
+		if ttl.subsec_nanos() > 0 {
+			TTL_ROUNDING_WARNING.call_once(|| {
+				warn!(ttl_ms = ttl.as_millis() as u64, "The server only supports whole-second TTLs, so TTLs are rounded up");
+			});
+		}
+
+		let secs = ttl.as_secs() + u64::from(ttl.subsec_nanos() > 0);
+		secs.min(u32::MAX.into()) as u32
+	})
+}
+
+/// Wipes the contents of the cache using a short-lived connection.
+pub fn wipe(paper_addr: &str, auth: Option<&str>) -> Result<(), PaperClientError> {
+	let mut client = PaperClient::new(paper_addr)?;
+
+	if let Some(token) = auth {
+		client.auth(token)?;
+	}
+
+	client.wipe()
+}
+
+/// Hashes a value so that the values of GET hits can be checked against
+/// the last value SET to their keys without keeping the values.
+fn hash_value(value: &[u8]) -> u64 {
+	let mut hasher = DefaultHasher::new();
+	value.hash(&mut hasher);
+
+	hasher.finish()
+}
+
+fn is_connection_error(err: &PaperClientError) -> bool {
+	matches!(
+		err,
+		PaperClientError::Disconnected | PaperClientError::UnreachableServer,
+	)
+}
+
+fn get_error_kind(err: &PaperClientError) -> ErrorKind {
+	match err {
+		PaperClientError::UnreachableServer => ErrorKind::Refused,
+		PaperClientError::Disconnected => ErrorKind::Disconnected,
+
+		PaperClientError::InvalidCommand
+			| PaperClientError::InvalidValue
+			| PaperClientError::InvalidResponse => ErrorKind::Protocol,
+
+		PaperClientError::ServerError(_) => ErrorKind::Server,
+		PaperClientError::CacheError(_) => ErrorKind::Cache,
+
+		PaperClientError::InvalidAddress
+			| PaperClientError::Internal => ErrorKind::Other,
+	}
+}
+
+impl Display for LoadModel {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		let s = match self {
+			LoadModel::Open => "open",
+			LoadModel::Closed => "closed",
+		};
+
+		write!(f, "{s}")
+	}
 }
 
 impl Display for ClientType {
@@ -181,6 +957,8 @@ impl Display for ClientType {
 		let s = match self {
 			ClientType::Lookaside => "lookaside",
 			ClientType::ReadThrough => "read-through",
+			ClientType::WriteThrough => "write-through",
+			ClientType::WriteBack => "write-back",
 		};
 
 		write!(f, "{s}")
@@ -0,0 +1,331 @@
+/*
+ * Copyright (c) Kia Shakiba
+ *
+ * This source code is licensed under the GNU AGPLv3 license found in the
+ * LICENSE file in the root directory of this source tree.
+ */
+
+use std::{
+	io,
+	path::Path,
+	collections::HashMap,
+};
+
+use kwik::{
+	table::{Table, Row, Align, Style},
+	file::{
+		FileReader,
+		csv::{CsvReader, ReadRow, RowData},
+	},
+};
+
+use crate::stats::Stats;
+
+/// The latencies of each operation at each percentile, loaded from a CSV
+/// file saved by `Stats::save_latency_percentiles`.
+struct PercentileTable {
+	operations: Vec<String>,
+	rows: Vec<(f64, Vec<f64>)>,
+}
+
+struct CsvRow {
+	fields: Vec<String>,
+}
+
+/// Prints the change in latency at each percentile from the baseline to
+/// the candidate percentile CSV file, flagging increases above the
+/// threshold percentage as regressions. Returns whether there were no
+/// regressions.
+pub fn compare<P>(baseline_path: P, candidate_path: P, threshold: f64) -> io::Result<bool>
+where
+	P: AsRef<Path>,
+{
+	let baseline = PercentileTable::from_path(baseline_path)?;
+
+	let candidate = PercentileTable::from_path(candidate_path)?
+		.operation_latencies()
+		.into_iter()
+		.collect::<HashMap<_, _>>();
+
+	println!("\n*** Comparison stats ***\n");
+
+	let mut table = Table::default();
+	let mut header = Row::default();
+
+	for label in ["Op", "Percentile", "Baseline", "Candidate", "Delta", "Change", ""] {
+		header = header.push(label, Align::Center, Style::Bold);
+	}
+
+	table.set_header(header);
+
+	let mut comparisons = 0;
+	let mut regressions = 0;
+
+	for (operation, baseline_latencies) in baseline.operation_latencies() {
+		let Some(candidate_latencies) = candidate.get(&operation) else {
+			continue;
+		};
+
+		for (percentile, baseline_latency) in baseline_latencies {
+			let Some(candidate_latency) = candidate_latencies
+				.iter()
+				.find(|(candidate_percentile, _)| *candidate_percentile == percentile)
+				.map(|(_, latency)| *latency)
+			else {
+				continue;
+			};
+
+			let delta = candidate_latency - baseline_latency;
+
+			let change = match baseline_latency > 0.0 {
+				true => delta / baseline_latency * 100.0,
+				false => 0.0,
+			};
+
+			let is_regression = change > threshold;
+
+			comparisons += 1;
+
+			if is_regression {
+				regressions += 1;
+			}
+
+			let row = Row::default()
+				.push(operation.clone(), Align::Center, Style::Bold)
+				.push(format!("p{percentile}"), Align::Center, Style::Normal)
+				.push(format!("{}us", baseline_latency.round()), Align::Center, Style::Normal)
+				.push(format!("{}us", candidate_latency.round()), Align::Center, Style::Normal)
+				.push(format!("{:+}us", delta.round()), Align::Center, Style::Normal)
+				.push(format!("{change:+.1}%"), Align::Center, Style::Normal)
+				.push(if is_regression { "REGRESSION" } else { "" }, Align::Center, Style::Bold);
+
+			table.add_row(row);
+		}
+	}
+
+	if comparisons == 0 {
+		return Err(io::Error::new(
+			io::ErrorKind::InvalidData,
+			"The CSV files have no operations and percentiles in common.",
+		));
+	}
+
+	let mut stdout = io::stdout().lock();
+	table.print(&mut stdout);
+
+	println!("\nRegressions:\t{regressions} of {comparisons} (threshold {threshold}%)");
+
+	Ok(regressions == 0)
+}
+
+impl PercentileTable {
+	fn from_path<P>(path: P) -> io::Result<Self>
+	where
+		P: AsRef<Path>,
+	{
+		// rows are read one at a time since iterating over the reader panics
+		// on rows it cannot read, such as rows whose number of columns
+		// differs from the header's, rather than returning an error
+		let mut reader = CsvReader::<CsvRow>::from_path(path)?;
+
+		let header = match reader.read_row() {
+			Ok(header) => header,
+
+			Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => {
+				return Err(io::Error::new(
+					io::ErrorKind::InvalidData,
+					"The CSV file is empty.",
+				));
+			},
+
+			Err(err) => return Err(err),
+		};
+
+		// files saved before the schema version column was added start
+		// with the percentile column
+		let has_schema_version = header.fields.first().is_some_and(|field| field == "schema_version");
+		let percentile_index = usize::from(has_schema_version);
+
+		if header.fields.get(percentile_index).is_none_or(|field| field != "Percentile") {
+			return Err(io::Error::new(
+				io::ErrorKind::InvalidData,
+				"The CSV file is not a percentile CSV file.",
+			));
+		}
+
+		let operations = header.fields[percentile_index + 1..].to_vec();
+		let mut rows = Vec::new();
+
+		loop {
+			let row = match reader.read_row() {
+				Ok(row) => row,
+				Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => break,
+
+				Err(err) => return Err(io::Error::new(
+					io::ErrorKind::InvalidData,
+					format!("{err}, which may have an invalid number of columns."),
+				)),
+			};
+
+			if has_schema_version {
+				let schema_version = parse_field(&row.fields[0])?;
+
+				if schema_version > f64::from(Stats::OUTPUT_SCHEMA_VERSION) {
+					return Err(io::Error::new(
+						io::ErrorKind::InvalidData,
+						format!("Unsupported schema version {schema_version}."),
+					));
+				}
+			}
+
+			let percentile = parse_field(&row.fields[percentile_index])?;
+
+			let latencies = row.fields[percentile_index + 1..]
+				.iter()
+				.map(|field| parse_field(field))
+				.collect::<io::Result<Vec<_>>>()?;
+
+			rows.push((percentile, latencies));
+		}
+
+		let table = PercentileTable {
+			operations,
+			rows,
+		};
+
+		Ok(table)
+	}
+
+	/// Returns each operation with its latency at each percentile.
+	fn operation_latencies(&self) -> Vec<(String, Vec<(f64, f64)>)> {
+		self.operations
+			.iter()
+			.enumerate()
+			.map(|(index, operation)| {
+				let latencies = self.rows
+					.iter()
+					.map(|(percentile, latencies)| (*percentile, latencies[index]))
+					.collect();
+
+				(operation.clone(), latencies)
+			})
+			.collect()
+	}
+}
+
+impl ReadRow for CsvRow {
+	fn from_row(row: &RowData) -> io::Result<Self> {
+		let fields = (0..row.len())
+			.map(|index| row.get(index).map(str::to_owned))
+			.collect::<io::Result<Vec<_>>>()?;
+
+		Ok(CsvRow { fields })
+	}
+}
+
+fn parse_field(field: &str) -> io::Result<f64> {
+	field
+		.parse::<f64>()
+		.map_err(|_| io::Error::new(
+			io::ErrorKind::InvalidData,
+			format!("Invalid number {field:?} in CSV file."),
+		))
+}
+
+#[cfg(test)]
+mod tests {
+	use std::{
+		fs,
+		io,
+		path::PathBuf,
+		process,
+	};
+
+	use crate::compare::{PercentileTable, compare};
+
+	#[test]
+	fn table_reads_schema_version_column() {
+		let path = write_csv("versioned.csv", "schema_version,Percentile,GET,SET\n2,50,10,20\n2,99,30,40\n");
+		let table = PercentileTable::from_path(&path);
+		fs::remove_file(&path).expect("Could not remove CSV file.");
+
+		let table = table.expect("Could not read CSV file.");
+
+		assert_eq!(table.operations, ["GET", "SET"]);
+		assert_eq!(table.rows, [(50.0, vec![10.0, 20.0]), (99.0, vec![30.0, 40.0])]);
+	}
+
+	#[test]
+	fn table_reads_file_without_schema_version() {
+		let path = write_csv("unversioned.csv", "Percentile,GET\n50,10\n99,30\n");
+		let table = PercentileTable::from_path(&path);
+		fs::remove_file(&path).expect("Could not remove CSV file.");
+
+		let table = table.expect("Could not read CSV file.");
+
+		assert_eq!(table.operations, ["GET"]);
+		assert_eq!(table.rows, [(50.0, vec![10.0]), (99.0, vec![30.0])]);
+	}
+
+	#[test]
+	fn table_rejects_newer_schema_version() {
+		let path = write_csv("newer.csv", "schema_version,Percentile,GET\n1000,50,10\n");
+		assert_invalid(PercentileTable::from_path(&path).map(|_| ()));
+		fs::remove_file(&path).expect("Could not remove CSV file.");
+	}
+
+	#[test]
+	fn table_rejects_mismatched_columns() {
+		let path = write_csv("columns.csv", "Percentile,GET,SET\n50,10\n");
+		assert_invalid(PercentileTable::from_path(&path).map(|_| ()));
+		fs::remove_file(&path).expect("Could not remove CSV file.");
+	}
+
+	#[test]
+	fn table_rejects_other_csv_file() {
+		let path = write_csv("other.csv", "Second,GET\n1,10\n");
+		assert_invalid(PercentileTable::from_path(&path).map(|_| ()));
+		fs::remove_file(&path).expect("Could not remove CSV file.");
+	}
+
+	#[test]
+	fn compare_flags_changes_over_threshold() {
+		let baseline = write_csv("baseline.csv", "schema_version,Percentile,GET,SET\n2,50,100,100\n2,99,200,200\n");
+		let candidate = write_csv("candidate.csv", "Percentile,SET,GET\n50,100,90\n99,200,220\n");
+
+		// the GET p99 is 10% slower
+		let is_pass_under = compare(&baseline, &candidate, 5.0).expect("Could not compare.");
+		let is_pass_over = compare(&baseline, &candidate, 10.0).expect("Could not compare.");
+
+		fs::remove_file(&baseline).expect("Could not remove CSV file.");
+		fs::remove_file(&candidate).expect("Could not remove CSV file.");
+
+		assert!(!is_pass_under);
+		assert!(is_pass_over);
+	}
+
+	#[test]
+	fn compare_rejects_files_with_nothing_in_common() {
+		let baseline = write_csv("disjoint-baseline.csv", "Percentile,GET\n50,100\n");
+		let candidate = write_csv("disjoint-candidate.csv", "Percentile,SET\n50,100\n");
+
+		let result = compare(&baseline, &candidate, 5.0);
+
+		fs::remove_file(&baseline).expect("Could not remove CSV file.");
+		fs::remove_file(&candidate).expect("Could not remove CSV file.");
+
+		assert_invalid(result.map(|_| ()));
+	}
+
+	fn write_csv(name: &str, contents: &str) -> PathBuf {
+		let path = std::env::temp_dir().join(format!("paper-benchmark-{}-{name}", process::id()));
+		fs::write(&path, contents).expect("Could not write CSV file.");
+
+		path
+	}
+
+	fn assert_invalid(result: io::Result<()>) {
+		let err = result.expect_err("Invalid CSV file was read.");
+		assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+	}
+}
@@ -0,0 +1,191 @@
+/*
+ * Copyright (c) Kia Shakiba
+ *
+ * This source code is licensed under the GNU AGPLv3 license found in the
+ * LICENSE file in the root directory of this source tree.
+ */
+
+use std::{
+	hash::{Hash, Hasher, DefaultHasher},
+	time::{Instant, Duration},
+};
+
+use crossbeam_channel::{Sender, SendError, TrySendError};
+use crate::client::ClientEvent;
+
+/// Sends events to the clients. With a single channel, events go to
+/// whichever client is free. With a channel per client, accesses are routed
+/// by key so that every access to a key is handled by the same client in
+/// trace order, and pings are spread round-robin.
+#[derive(Clone)]
+pub struct Dispatcher {
+	senders: Vec<Sender<(ClientEvent, Instant)>>,
+	next: usize,
+
+	sends: u64,
+	stalled_sends: u64,
+	stall_time: Duration,
+}
+
+impl Dispatcher {
+	pub fn new(senders: Vec<Sender<(ClientEvent, Instant)>>) -> Self {
+		assert!(!senders.is_empty(), "Dispatcher must have at least one sender.");
+
+		Dispatcher {
+			senders,
+			next: 0,
+
+			sends: 0,
+			stalled_sends: 0,
+			stall_time: Duration::ZERO,
+		}
+	}
+
+	pub fn send(&mut self, event: ClientEvent) -> Result<(), SendError<(ClientEvent, Instant)>> {
+		let index = match &event {
+			ClientEvent::Access(access, _) | ClientEvent::Warmup(access) => {
+				let mut hasher = DefaultHasher::new();
+				access.key.hash(&mut hasher);
+
+				(hasher.finish() % self.senders.len() as u64) as usize
+			},
+
+			ClientEvent::Ping | ClientEvent::WarmupPing => {
+				self.next = (self.next + 1) % self.senders.len();
+				self.next
+			},
+		};
+
+		self.sends += 1;
+
+		// a send only blocks when the clients have fallen behind and the
+		// channel is full, so the time spent blocked is measured then
+		let event = match self.senders[index].try_send((event, Instant::now())) {
+			Ok(()) => return Ok(()),
+			Err(TrySendError::Full((event, _))) => event,
+			Err(TrySendError::Disconnected(event)) => return Err(SendError(event)),
+		};
+
+		let start = Instant::now();
+		let result = self.senders[index].send((event, Instant::now()));
+
+		self.stalled_sends += 1;
+		self.stall_time += start.elapsed();
+
+		result
+	}
+
+	pub fn sends(&self) -> u64 {
+		self.sends
+	}
+
+	/// Returns the number of sends which blocked on a full channel.
+	pub fn stalled_sends(&self) -> u64 {
+		self.stalled_sends
+	}
+
+	/// Returns the total time spent blocked on full channels.
+	pub fn stall_time(&self) -> Duration {
+		self.stall_time
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::time::Instant;
+	use crossbeam_channel::{Receiver, unbounded};
+
+	use crate::{
+		access::{Access, Command},
+		client::ClientEvent,
+		dispatcher::Dispatcher,
+	};
+
+	const CLIENTS: usize = 8;
+
+	#[test]
+	fn same_key_reaches_same_client() {
+		let (mut dispatcher, receivers) = get_dispatcher();
+
+		for round in 0..10 {
+			for key in 0..100 {
+				let event = match round % 2 {
+					0 => ClientEvent::Access(get_access(key), None),
+					_ => ClientEvent::Warmup(get_access(key)),
+				};
+
+				dispatcher.send(event).expect("Could not send access.");
+			}
+		}
+
+		let mut clients = vec![None; 100];
+
+		for (client, receiver) in receivers.iter().enumerate() {
+			for (event, _) in receiver.try_iter() {
+				let (ClientEvent::Access(access, _) | ClientEvent::Warmup(access)) = event else {
+					panic!("Client received a ping.");
+				};
+
+				let key = access.key.parse::<usize>().expect("Invalid key.");
+				assert_eq!(*clients[key].get_or_insert(client), client);
+			}
+		}
+
+		assert!(clients.iter().all(Option::is_some));
+	}
+
+	#[test]
+	fn keys_are_spread_across_clients() {
+		let (mut dispatcher, receivers) = get_dispatcher();
+
+		for key in 0..1000 {
+			dispatcher.send(ClientEvent::Access(get_access(key), None))
+				.expect("Could not send access.");
+		}
+
+		assert!(receivers.iter().all(|receiver| !receiver.is_empty()));
+	}
+
+	#[test]
+	fn pings_are_sent_round_robin() {
+		let (mut dispatcher, receivers) = get_dispatcher();
+
+		for _ in 0..CLIENTS * 3 {
+			dispatcher.send(ClientEvent::Ping).expect("Could not send ping.");
+		}
+
+		assert!(receivers.iter().all(|receiver| receiver.len() == 3));
+		assert_eq!(dispatcher.sends(), CLIENTS as u64 * 3);
+	}
+
+	#[test]
+	fn single_client_receives_everything() {
+		let (sender, receiver) = unbounded();
+		let mut dispatcher = Dispatcher::new(vec![sender]);
+
+		dispatcher.send(ClientEvent::Access(get_access(1), None)).expect("Could not send access.");
+		dispatcher.send(ClientEvent::Ping).expect("Could not send ping.");
+
+		assert_eq!(receiver.len(), 2);
+	}
+
+	fn get_dispatcher() -> (Dispatcher, Vec<Receiver<(ClientEvent, Instant)>>) {
+		let (senders, receivers) = (0..CLIENTS)
+			.map(|_| unbounded())
+			.unzip::<_, _, Vec<_>, Vec<_>>();
+
+		(Dispatcher::new(senders), receivers)
+	}
+
+	fn get_access(key: usize) -> Access {
+		Access {
+			timestamp: 0,
+			command: Command::Get,
+
+			key: key.to_string(),
+			value: Box::default(),
+
+			ttl: None,
+		}
+	}
+}
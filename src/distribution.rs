@@ -0,0 +1,121 @@
+/*
+ * Copyright (c) Kia Shakiba
+ *
+ * This source code is licensed under the GNU AGPLv3 license found in the
+ * LICENSE file in the root directory of this source tree.
+ */
+
+use statrs::statistics::{Data, Distribution, OrderStatistics, Min, Max};
+use tdigest::TDigest;
+
+// the maximum number of centroids kept by each digest
+const DIGEST_SIZE: usize = 1000;
+
+/// A distribution of latencies in microseconds, either computed exactly
+/// from every stored latency or estimated from a streaming digest.
+pub enum LatencyDistribution {
+	Exact(Data<Vec<f64>>),
+	Streaming(LatencyDigest),
+}
+
+/// Estimates latency quantiles in constant memory.
+#[derive(Debug, Clone)]
+pub struct LatencyDigest {
+	digest: TDigest,
+	sum_squares: f64,
+}
+
+impl LatencyDistribution {
+	pub fn len(&self) -> usize {
+		match self {
+			LatencyDistribution::Exact(data) => data.len(),
+			LatencyDistribution::Streaming(digest) => digest.digest.count() as usize,
+		}
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.len() == 0
+	}
+
+	pub fn mean(&self) -> f64 {
+		match self {
+			LatencyDistribution::Exact(data) => data.mean().unwrap_or(0.0),
+			LatencyDistribution::Streaming(digest) => digest.digest.mean().unwrap_or(0.0),
+		}
+	}
+
+	pub fn min(&self) -> f64 {
+		match self {
+			LatencyDistribution::Exact(data) => data.min(),
+			LatencyDistribution::Streaming(digest) => digest.digest.min().unwrap_or(0.0),
+		}
+	}
+
+	pub fn max(&self) -> f64 {
+		match self {
+			LatencyDistribution::Exact(data) => data.max(),
+			LatencyDistribution::Streaming(digest) => digest.digest.max().unwrap_or(0.0),
+		}
+	}
+
+	pub fn std_dev(&self) -> f64 {
+		match self {
+			LatencyDistribution::Exact(data) => data.std_dev().unwrap_or(0.0),
+			LatencyDistribution::Streaming(digest) => digest.std_dev(),
+		}
+	}
+
+	pub fn quantile(&mut self, quantile: f64) -> f64 {
+		match self {
+			LatencyDistribution::Exact(data) => data.quantile(quantile),
+
+			LatencyDistribution::Streaming(digest) => digest.digest
+				.estimate_quantile(quantile)
+				.unwrap_or(0.0),
+		}
+	}
+}
+
+impl LatencyDigest {
+	pub fn push(&mut self, latency: f64) {
+		self.digest.push(latency);
+		self.sum_squares += latency * latency;
+	}
+
+	/// Compresses any buffered latencies so the digest can be queried.
+	pub fn flush(&mut self) {
+		self.digest.flush();
+	}
+
+	pub fn merge(mut self, mut rhs: Self) -> Self {
+		self.flush();
+		rhs.flush();
+
+		LatencyDigest {
+			digest: TDigest::merge_digests(vec![self.digest, rhs.digest]),
+			sum_squares: self.sum_squares + rhs.sum_squares,
+		}
+	}
+
+	fn std_dev(&self) -> f64 {
+		let count = self.digest.count();
+
+		if count < 2.0 {
+			return 0.0;
+		}
+
+		let sum = self.digest.sum();
+		let variance = (self.sum_squares - sum * sum / count) / (count - 1.0);
+
+		variance.max(0.0).sqrt()
+	}
+}
+
+impl Default for LatencyDigest {
+	fn default() -> Self {
+		LatencyDigest {
+			digest: TDigest::new_with_size(DIGEST_SIZE),
+			sum_squares: 0.0,
+		}
+	}
+}
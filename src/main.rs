@@ -8,202 +8,2184 @@
 mod access;
 mod client;
 mod stats;
+mod trace;
+mod progress;
+mod pacer;
+mod trace_stats;
+mod distribution;
+mod dispatcher;
+mod metrics;
+mod scheduler;
+mod compare;
+mod synthetic;
+mod raw;
+mod timer;
+mod warmup;
 
 use std::{
+	env,
+	fs,
+	iter,
+	io::{self, IsTerminal, Write},
 	thread,
-	sync::Arc,
-	io::{self, Seek, SeekFrom},
+	process,
+	sync::{
+		Arc,
+		atomic::{AtomicBool, Ordering},
+	},
+	net::{SocketAddr, Ipv6Addr, ToSocketAddrs},
 	path::{Path, PathBuf},
-	time::Duration,
+	time::{Instant, Duration},
 };
 
-use clap::Parser;
-use crossbeam_channel::bounded;
+use clap::{Parser, Subcommand, CommandFactory, ValueEnum, ArgGroup, error::ErrorKind};
+use crossbeam_channel::{RecvTimeoutError, bounded, unbounded};
+use tracing::{Level, info, warn, error};
+use rand::{SeedableRng, rngs::StdRng, seq::SliceRandom};
 
-use kwik::{
-	fmt,
-	file::{
-		FileReader,
-		binary::{BinaryReader, SizedChunk},
-	},
-	progress::{Progress, Tag},
-};
+use kwik::fmt;
 
 use crate::{
-	client::{BenchmarkClient, ClientType, ClientEvent},
-	access::Access,
-	stats::Stats,
+	access::{Access, Command},
+	client::{BenchmarkClient, ClientType, ClientEvent, LoadModel, ErrorPolicy},
+	stats::{Stats, StatsMemory, DEFAULT_PERCENTILES},
+	trace::{TraceReader, TraceFormat, TtlUnit},
+	progress::{BenchmarkProgress, ProgressFormat},
+	pacer::Pacer,
+	trace_stats::TraceStats,
+	distribution::LatencyDigest,
+	dispatcher::Dispatcher,
+	scheduler::Scheduler,
+	synthetic::{SyntheticWorkload, KeyDistribution, KeyDistributionType},
+	metrics::LiveMetrics,
+	raw::RawWriter,
+	timer::TimerType,
+	warmup::WarmupMonitor,
 };
 
 const PING_TEST_COUNT: u64 = 1_000_000;
+const SYNTHETIC_VALUE_SIZE: u32 = 1024;
+const STATS_MEMORY_LOG_INTERVAL: Duration = Duration::from_secs(10);
 
 #[derive(Parser)]
-#[command(author, version, about, long_about = None)]
+#[command(author, version, about, long_about = None, args_conflicts_with_subcommands = true)]
+#[command(group(ArgGroup::new("named_outputs").args(["output_dir", "append_summary"]).multiple(true)))]
 struct Args {
+	#[command(subcommand)]
+	command: Option<BenchmarkCommand>,
+
 	#[arg(long, default_value = "127.0.0.1")]
 	host: String,
 
 	#[arg(long, default_value_t = 3145)]
-	port: u32,
+	port: u16,
 
+	/// The auth token. Takes precedence over --auth-file and the PAPER_AUTH
+	/// environment variable
 	#[arg(short, long)]
 	auth: Option<String>,
 
-	#[arg(short, long)]
-	trace_path: Option<PathBuf>,
+	/// A file containing the auth token. Takes precedence over the
+	/// PAPER_AUTH environment variable
+	#[arg(long, conflicts_with = "auth")]
+	auth_file: Option<PathBuf>,
+
+	/// One or more trace files, which are processed in order as a single
+	/// trace. Files ending in .gz or .zst are decompressed as they are read
+	#[arg(short, long, value_delimiter = ',')]
+	trace_path: Vec<PathBuf>,
+
+	/// The format of legacy traces without a header. Traces with a header,
+	/// including those from stdin, are read in the format it names
+	#[arg(long, default_value_t = TraceFormat::V1)]
+	trace_format: TraceFormat,
+
+	/// Check that every record of the trace can be read before the benchmark
+	/// starts
+	#[arg(long, requires = "trace_path")]
+	validate_trace: bool,
+
+	/// Stop after this many accesses have been sent, not counting warmup
+	/// accesses
+	#[arg(long, requires = "trace_path", value_parser = clap::value_parser!(u64).range(1..))]
+	max_requests: Option<u64>,
+
+	/// Replay only the accesses of this command from the trace
+	#[arg(long, requires = "trace_path")]
+	only: Option<Command>,
+
+	/// Verify the checksum of every record before the benchmark starts.
+	/// Requires a v5 trace
+	#[arg(long, requires = "trace_path")]
+	verify_checksum: bool,
+
+	/// Generate GET and SET accesses over a key space instead of replaying
+	/// a trace. SETs use --value-size, or 1 KiB values if it is not set
+	#[arg(long, conflicts_with_all = ["trace_path", "native_time", "shuffle", "repeat"])]
+	synthetic: bool,
+
+	/// The number of keys accessed by the synthetic workload
+	#[arg(long, default_value_t = 1_000_000, requires = "synthetic", value_parser = clap::value_parser!(u64).range(1..))]
+	keys: u64,
+
+	/// The fraction of synthetic accesses which are GETs
+	#[arg(long, default_value_t = 0.8, requires = "synthetic", value_parser = parse_fraction)]
+	read_ratio: f64,
+
+	/// The number of synthetic accesses to generate
+	#[arg(long, default_value_t = 1_000_000, requires = "synthetic")]
+	requests: u64,
+
+	/// How the keys of synthetic accesses are chosen
+	#[arg(long, default_value = "uniform", requires = "synthetic")]
+	key_dist: KeyDistributionType,
+
+	/// The skew of the Zipfian key distribution
+	#[arg(long, default_value_t = 0.99, requires = "synthetic", value_parser = parse_zipf_skew)]
+	zipf_skew: f64,
+
+	/// Choose keys from a Zipfian distribution with this skew. Shorthand
+	/// for --key-dist zipf --zipf-skew <THETA>
+	#[arg(
+		long,
+		value_name = "THETA",
+		requires = "synthetic",
+		conflicts_with_all = ["key_dist", "zipf_skew"],
+		value_parser = parse_zipf_skew,
+	)]
+	zipf: Option<f64>,
+
+	/// The fraction of keys which are hot in the hot-set key distribution
+	#[arg(long, default_value_t = 0.2, requires = "synthetic", value_parser = parse_fraction)]
+	hot_set_keys: f64,
+
+	/// The fraction of accesses sent to the hot keys in the hot-set key
+	/// distribution
+	#[arg(long, default_value_t = 0.8, requires = "synthetic", value_parser = parse_fraction)]
+	hot_set_traffic: f64,
+
+	/// Print statistics describing the trace without connecting to the server
+	#[arg(long, requires = "trace_path")]
+	dry_run: bool,
 
 	#[arg(short, long, default_value_t = 4)]
 	clients: u32,
 
+	/// The number of connections each client opens and issues its requests
+	/// over in turn
+	#[arg(long, default_value_t = 1, value_parser = clap::value_parser!(u32).range(1..))]
+	connection_pool_size: u32,
+
+	/// Keep the cache's contents instead of wiping it before the benchmark
+	#[arg(long)]
+	no_wipe: bool,
+
+	/// Allow wiping a cache which is not on this machine without asking
+	#[arg(long, conflicts_with = "no_wipe")]
+	confirm_wipe: bool,
+
 	#[arg(short, long)]
 	native_time: bool,
 
+	/// Measure latencies from when each access was scheduled by the trace
+	/// rather than from when it was sent, correcting for coordinated omission
+	#[arg(long, requires = "native_time")]
+	correct_omission: bool,
+
+	/// How many times faster than native time to replay the trace, e.g.,
+	/// 2 halves every gap between accesses. Zero replays as fast as possible
+	#[arg(long, default_value_t = 1.0, requires = "native_time", value_parser = parse_time_scale)]
+	time_scale: f64,
+
+	/// How many milliseconds an access may go back in time before the trace
+	/// is rejected. Smaller backward jumps are sent immediately and counted
+	#[arg(long, default_value_t = 0, requires = "native_time")]
+	allow_skew: u64,
+
+	/// The unit of the trace's TTLs. Defaults to seconds for v1 to v3
+	/// traces and milliseconds for v4 and v5 traces. The server only
+	/// supports whole seconds, so TTLs are rounded up when sent
+	#[arg(long, requires = "native_time")]
+	ttl_unit: Option<TtlUnit>,
+
+	/// Replay the trace's accesses in a random order, seeded by --seed.
+	/// Each replay of the trace is loaded into memory to be shuffled
+	#[arg(long, conflicts_with = "native_time")]
+	shuffle: bool,
+
+	/// The number of times to replay the trace
+	#[arg(long, default_value_t = 1, value_parser = clap::value_parser!(u64).range(1..))]
+	repeat: u64,
+
+	/// The number of pings to perform before any accesses are processed.
+	/// Defaults to 1,000,000 when there is no trace or synthetic workload
+	/// and to none otherwise
+	#[arg(long)]
+	ping_count: Option<u64>,
+
+	/// Skip the ping phase
+	#[arg(long, conflicts_with = "ping_count")]
+	no_ping: bool,
+
+	/// The number of warmup requests sent before each phase, which are
+	/// pings before the ping phase and accesses before the access phase
+	#[arg(long, default_value_t = 0)]
+	warmup: u64,
+
+	/// End the warmup early once the p99 latency of consecutive windows of
+	/// warmup requests changes by at most this percentage, in which case
+	/// --warmup is the most requests the warmup may take
+	#[arg(long, requires = "warmup", value_parser = parse_warmup_threshold)]
+	warmup_until_stable: Option<f64>,
+
+	/// Stop sending accesses after this many seconds
+	#[arg(long, value_parser = clap::value_parser!(u64).range(1..))]
+	duration: Option<u64>,
+
+	/// Send accesses at this many queries per second rather than as fast as
+	/// possible
+	#[arg(long, value_parser = clap::value_parser!(u32).range(1..), conflicts_with = "native_time")]
+	qps: Option<u32>,
+
+	/// Whether events are offered at their scheduled time or only as fast
+	/// as the clients complete them. The open load model requires
+	/// --native-time or --qps to schedule the events
+	#[arg(long, default_value_t = LoadModel::Closed)]
+	load_model: LoadModel,
+
+	/// The number of events buffered between the trace and the clients, or
+	/// between the trace and each client under --key-affinity. Defaults to
+	/// the number of clients, or one per client under --key-affinity. Events
+	/// still buffered when --duration or --max-requests stops the benchmark
+	/// are processed while draining. Unbounded under the open load model
+	#[arg(long, value_parser = clap::value_parser!(u32).range(1..))]
+	channel_capacity: Option<u32>,
+
 	#[arg(long, default_value_t = ClientType::Lookaside)]
 	client_type: ClientType,
 
+	/// Weighted client types, e.g. lookaside=3,read-through=1. The clients
+	/// are split between the types in proportion to their weights
+	#[arg(long, value_delimiter = ',', value_parser = parse_client_mix_entry, conflicts_with = "client_type")]
+	client_mix: Vec<(ClientType, u32)>,
+
+	/// Route every access to a key to the same client so that accesses to
+	/// each key are sent in trace order
+	#[arg(long)]
+	key_affinity: bool,
+
+	/// Check that every GET hit returns the value last SET to its key and
+	/// report any mismatches. Requires --key-affinity so that each key's
+	/// accesses are handled in order by one client, and lookaside clients
+	#[arg(long, requires = "key_affinity")]
+	verify_values: bool,
+
+	/// Split GET latencies between the given number of most frequently
+	/// accessed keys in the trace and the remaining keys
+	#[arg(long, requires = "trace_path", value_parser = clap::value_parser!(u64).range(1..))]
+	hot_keys: Option<u64>,
+
+	/// The latency in microseconds of the simulated backing store write
+	/// which follows each write-through SET
+	#[arg(long, default_value_t = 0)]
+	backing_store_latency: u64,
+
+	/// Send SET values of this many bytes instead of the trace's value sizes
+	#[arg(long)]
+	value_size: Option<u32>,
+
+	/// The TTL in seconds of objects populated into the cache after a
+	/// read-through miss, overriding the trace's TTL
+	#[arg(long, value_parser = clap::value_parser!(u32).range(1..))]
+	populate_ttl: Option<u32>,
+
+	/// The number of SETs a write-back client buffers before flushing
+	#[arg(long, default_value_t = 16, value_parser = clap::value_parser!(u64).range(1..))]
+	write_back_batch_size: u64,
+
+	/// The longest time in milliseconds a write-back client waits between flushes
+	#[arg(long, default_value_t = 100)]
+	write_back_interval: u64,
+
+	/// A string prepended to every key so that benchmarks sharing a cache do
+	/// not collide
+	#[arg(long)]
+	key_prefix: Option<String>,
+
+	/// Stop a client once it has waited this many seconds for an access
+	#[arg(long)]
+	client_idle_timeout: Option<u64>,
+
+	/// Abort once a client fails more than this many requests. Implies
+	/// --abort-on-error
+	#[arg(long, conflicts_with = "continue_on_error")]
+	max_errors: Option<u64>,
+
+	/// Abort once a client fails more requests than --max-errors, which
+	/// defaults to zero
+	#[arg(long, conflicts_with = "continue_on_error")]
+	abort_on_error: bool,
+
+	/// Log and count failed requests without aborting. This is the default
+	/// unless --abort-on-error or --max-errors is supplied
+	#[arg(long)]
+	continue_on_error: bool,
+
+	/// The number of times a client tries to reconnect after losing its
+	/// connection. Zero disables reconnecting
+	#[arg(long, default_value_t = 5)]
+	max_reconnect_attempts: u32,
+
+	/// The percentiles to print and save, e.g. 50,99,99.9
+	#[arg(long, value_delimiter = ',', value_parser = parse_percentile)]
+	percentiles: Option<Vec<f64>>,
+
+	/// Also print brief stats for each client
+	#[arg(long)]
+	per_client_stats: bool,
+
+	/// The p50 latency in microseconds no operation may exceed. The process
+	/// exits with a non-zero code if any SLO is violated
+	#[arg(long)]
+	slo_p50: Option<f64>,
+
+	/// The p99 latency in microseconds no operation may exceed
+	#[arg(long)]
+	slo_p99: Option<f64>,
+
+	/// The p99.9 latency in microseconds no operation may exceed
+	#[arg(long)]
+	slo_p999: Option<f64>,
+
+	/// The maximum latency in microseconds no operation may exceed
+	#[arg(long)]
+	slo_max: Option<f64>,
+
+	/// The GET hit rate in percent the run is expected to reach. The process
+	/// exits with a non-zero code if the measured hit rate is outside of
+	/// --hit-rate-tolerance
+	#[arg(long, value_parser = parse_hit_rate)]
+	expect_hit_rate: Option<f64>,
+
+	/// How many percentage points the hit rate may differ from
+	/// --expect-hit-rate
+	#[arg(long, default_value_t = 1.0, requires = "expect_hit_rate")]
+	hit_rate_tolerance: f64,
+
+	/// Also print a single table summarizing every operation
+	#[arg(long)]
+	combined_table: bool,
+
+	/// The fraction of requests in (0, 1] whose latencies are stored.
+	/// Request counts and sizes are always exact
+	#[arg(long, value_parser = parse_sample_rate)]
+	sample_rate: Option<f64>,
+
+	/// Stop the benchmark early once the stored latencies, TTLs, verified
+	/// value hashes, and hot keys are estimated to use more than this many
+	/// MiB
+	#[arg(long, conflicts_with = "streaming_percentiles", value_parser = clap::value_parser!(u64).range(1..))]
+	max_stats_memory: Option<u64>,
+
+	/// Estimate percentiles with streaming digests instead of storing every
+	/// latency, which bounds memory use on long runs. Outputs which need the
+	/// individual latencies cannot be used
+	#[arg(
+		long,
+		conflicts_with_all = [
+			"sample_rate",
+			"per_client_stats",
+			"output_plot",
+			"output_cdf_plot",
+			"output_histogram",
+			"output_throughput",
+			"output_percentile_timeseries",
+		],
+	)]
+	streaming_percentiles: bool,
+
+	/// Exclude requests which drained the events still queued when the
+	/// trace finished from every other output
+	#[arg(long, conflicts_with = "streaming_percentiles")]
+	exclude_drain: bool,
+
+	/// Count the requests of each type which take longer than this many
+	/// microseconds
+	#[arg(long, value_parser = clap::value_parser!(u64).range(1..))]
+	op_slow_threshold: Option<u64>,
+
+	/// The clock used to measure request latencies
+	#[arg(long, default_value = "std")]
+	timer: TimerType,
+
+	/// Seeds every source of randomness so that the keys, commands, and
+	/// order of the issued accesses are the same across runs. Which client
+	/// sends each access still varies with timing, and so do the random
+	/// bytes of --value-size values, which each client generates from its
+	/// own seed, along with other timing-dependent results such as latencies
+	#[arg(long)]
+	seed: Option<u64>,
+
+	/// The address on which to serve Prometheus metrics at /metrics while
+	/// the benchmark runs, e.g. 0.0.0.0:9100
+	#[arg(long)]
+	metrics_addr: Option<SocketAddr>,
+
+	/// How progress is reported. Defaults to a progress bar when stdout is
+	/// a terminal and JSON lines otherwise
+	#[arg(long)]
+	progress_format: Option<ProgressFormat>,
+
+	/// The most verbose level of diagnostics logged to stderr, one of
+	/// error, warn, info, debug, or trace. Results are printed to stdout
+	#[arg(long, default_value_t = Level::INFO)]
+	log_level: Level,
+
+	/// Save the latency of each operation at each percentile to a CSV file
 	#[arg(long)]
 	output_csv: Option<PathBuf>,
 
+	/// Save a plot of each operation's moving average latency over time
 	#[arg(long)]
 	output_plot: Option<PathBuf>,
+
+	/// The number of moving average windows the latency plot is split into
+	#[arg(long, default_value_t = 50, value_parser = clap::value_parser!(u32).range(1..))]
+	plot_resolution: u32,
+
+	/// Save a plot of each operation's latency against its percentile
+	#[arg(long)]
+	output_percentile_plot: Option<PathBuf>,
+
+	/// Use a linear rather than log scale for the percentile plot's x-axis
+	#[arg(long)]
+	linear_percentile_plot: bool,
+
+	/// Save a plot of the cumulative fraction of requests completed within
+	/// each latency
+	#[arg(long)]
+	output_cdf_plot: Option<PathBuf>,
+
+	/// Save each operation's latency histogram to a CSV file
+	#[arg(long)]
+	output_histogram: Option<PathBuf>,
+
+	/// The number of log-spaced histogram buckets per power of ten
+	#[arg(long, default_value_t = 10, value_parser = clap::value_parser!(u32).range(1..))]
+	histogram_buckets_per_decade: u32,
+
+	/// Save a summary of the run to a JSON file
+	#[arg(long)]
+	output_json: Option<PathBuf>,
+
+	/// Save the number of operations completed in each second to a CSV file
+	#[arg(long)]
+	output_throughput: Option<PathBuf>,
+
+	/// Save the p50 and p99 GET and SET latencies within each second
+	#[arg(long)]
+	output_percentile_timeseries: Option<PathBuf>,
+
+	/// Stream the start offset, operation, latency, size, and hit of every
+	/// request to a CSV file as the benchmark runs
+	#[arg(long)]
+	output_raw: Option<PathBuf>,
+
+	/// Save each operation's latencies to an HdrHistogram interval log
+	#[cfg(feature = "hdrhistogram")]
+	#[arg(long, conflicts_with = "streaming_percentiles")]
+	output_hdr: Option<PathBuf>,
+
+	/// A directory to write every output to, named after the run. Outputs
+	/// given their own paths are written there instead
+	#[arg(long)]
+	output_dir: Option<PathBuf>,
+
+	/// Append a row summarizing the run to a CSV file shared across runs
+	#[arg(long)]
+	append_summary: Option<PathBuf>,
+
+	/// Compare two CSV files saved by --output-csv instead of running a
+	/// benchmark, printing the change in latency at each percentile
+	#[arg(long, num_args = 2, value_names = ["BASELINE", "CANDIDATE"])]
+	compare: Option<Vec<PathBuf>>,
+
+	/// The percentage increase in latency from the baseline which --compare
+	/// flags as a regression
+	#[arg(long, default_value_t = 10.0, requires = "compare")]
+	regression_threshold: f64,
+
+	/// The prefix of the outputs written to --output-dir and the label of
+	/// the row appended to --append-summary
+	#[arg(long, requires = "named_outputs")]
+	run_name: Option<String>,
+}
+
+#[derive(Subcommand)]
+enum BenchmarkCommand {
+	/// Write a v1 binary trace of synthetic accesses instead of running a
+	/// benchmark
+	Generate(GenerateArgs),
+}
+
+#[derive(clap::Args)]
+struct GenerateArgs {
+	/// The path of the trace file to write
+	#[arg(short, long)]
+	output: PathBuf,
+
+	/// The number of accesses to generate
+	#[arg(long, default_value_t = 1_000_000)]
+	requests: u64,
+
+	/// The number of keys accessed
+	#[arg(long, default_value_t = 1_000_000, value_parser = clap::value_parser!(u64).range(1..))]
+	keys: u64,
+
+	/// The fraction of accesses which are GETs
+	#[arg(long, default_value_t = 0.8, value_parser = parse_fraction)]
+	read_ratio: f64,
+
+	/// The size in bytes of the value of each SET
+	#[arg(long, default_value_t = SYNTHETIC_VALUE_SIZE)]
+	value_size: u32,
+
+	/// How the keys of accesses are chosen
+	#[arg(long, default_value = "uniform")]
+	key_dist: KeyDistributionType,
+
+	/// The skew of the Zipfian key distribution
+	#[arg(long, default_value_t = 0.99, value_parser = parse_zipf_skew)]
+	zipf_skew: f64,
+
+	/// The fraction of keys which are hot in the hot-set key distribution
+	#[arg(long, default_value_t = 0.2, value_parser = parse_fraction)]
+	hot_set_keys: f64,
+
+	/// The fraction of accesses sent to the hot keys in the hot-set key
+	/// distribution
+	#[arg(long, default_value_t = 0.8, value_parser = parse_fraction)]
+	hot_set_traffic: f64,
+
+	/// The number of milliseconds between the timestamps of consecutive
+	/// accesses
+	#[arg(long, default_value_t = 1)]
+	interval: u64,
+
+	/// The TTL in seconds of every SET. SETs have no TTL if not set
+	#[arg(long, value_parser = clap::value_parser!(u32).range(1..))]
+	ttl: Option<u32>,
+
+	/// Seeds the generated keys and commands so that the trace is the same
+	/// across runs
+	#[arg(long)]
+	seed: Option<u64>,
 }
 
 fn main() {
-	let args = Args::parse();
+	let mut args = Args::parse();
+
+	tracing_subscriber::fmt()
+		.with_writer(io::stderr)
+		.with_max_level(args.log_level)
+		.with_target(false)
+		.init();
+
+	if let Some(BenchmarkCommand::Generate(generate_args)) = &args.command {
+		generate_trace(generate_args);
+		return;
+	}
 
 	assert!(args.clients > 0);
 
-	let paper_addr = format!("paper://{}:{}", args.host, args.port);
-	let paper_addr = Arc::new(paper_addr);
+	if let Some(paths) = &args.compare {
+		let passed = compare::compare(&paths[0], &paths[1], args.regression_threshold)
+			.expect("Could not compare results.");
 
-	let (sender, receiver) = bounded::<ClientEvent>(args.clients as usize);
+		process::exit(if passed { 0 } else { 1 });
+	}
 
-	println!("Client type: {}", args.client_type);
-	println!("Initializing {} client(s)", args.clients);
+	if let Some(zipf_skew) = args.zipf {
+		args.key_dist = KeyDistributionType::Zipf;
+		args.zipf_skew = zipf_skew;
+	}
 
-	let clients = (0..args.clients)
-		.map(|_| {
-			let paper_addr = paper_addr.clone();
-			let receiver = receiver.clone();
+	let has_accesses = !args.trace_path.is_empty() || args.synthetic;
+	let ping_count = get_ping_count(&args);
 
-			BenchmarkClient::new(&paper_addr, args.auth.clone(), receiver)
-				.expect("Could not create client.")
-				.with_client_type(args.client_type)
-		})
-		.collect::<Vec<BenchmarkClient>>();
+	if !has_accesses && ping_count == 0 {
+		Args::command()
+			.error(ErrorKind::MissingRequiredArgument, "the ping phase is skipped, so a --trace-path or --synthetic must be supplied")
+			.exit();
+	}
 
-	let tasks = clients
-		.into_iter()
-		.map(|mut client| thread::spawn(move || client.run()))
-		.collect::<Vec<_>>();
+	// only lookaside clients record the values they SET, so the other client
+	// types would report no mismatches without having checked anything
+	if args.verify_values
+		&& get_client_types(&args).iter().any(|client_type| *client_type != ClientType::Lookaside)
+	{
+		Args::command()
+			.error(ErrorKind::ArgumentConflict, "--verify-values can only be used with lookaside clients")
+			.exit();
+	}
 
-	if args.trace_path.is_none() {
-		println!("\nPerforming {} pings", fmt::number(PING_TEST_COUNT));
+	if args.warmup_until_stable.is_some() && args.warmup == 0 {
+		Args::command()
+			.error(ErrorKind::ValueValidation, "--warmup-until-stable requires a --warmup greater than 0")
+			.exit();
+	}
 
-		let mut progress = Progress::new(PING_TEST_COUNT)
-			.with_tag(Tag::Tps)
-			.with_tag(Tag::Eta)
-			.with_tag(Tag::Time);
+	let ping_warmup = match ping_count > 0 {
+		true => args.warmup,
+		false => 0,
+	};
 
-		for _ in 0..PING_TEST_COUNT {
-			sender.send(ClientEvent::Ping)
-				.expect("Could not send ping to client.");
+	let access_warmup = match has_accesses {
+		true => args.warmup,
+		false => 0,
+	};
 
-			progress.tick(1);
-		}
+	// without pacing, an open load model would queue the whole trace in
+	// the unbounded channels at once
+	if args.load_model == LoadModel::Open && !args.native_time && args.qps.is_none() {
+		Args::command()
+			.error(ErrorKind::MissingRequiredArgument, "--load-model open requires --native-time or --qps to pace the requests")
+			.exit();
+	}
+
+	if args.trace_path.len() > 1 && args.trace_path.iter().any(|path| is_stdin(path)) {
+		Args::command()
+			.error(ErrorKind::ArgumentConflict, "a trace from stdin cannot be combined with other trace paths")
+			.exit();
 	}
 
-	if let Some(trace_path) = &args.trace_path {
+	if args.trace_path.iter().any(|path| is_stdin(path)) {
 		if args.native_time {
-			let timespan = get_trace_timespan(trace_path)
-				.expect("Invalid trace path.");
+			Args::command()
+				.error(ErrorKind::ArgumentConflict, "--native-time cannot be used with a trace from stdin")
+				.exit();
+		}
 
-			println!("\nUsing native access time.");
-			println!("Total trace timestamp: {}", fmt::timespan(timespan));
+		if args.hot_keys.is_some() {
+			Args::command()
+				.error(ErrorKind::ArgumentConflict, "--hot-keys cannot be used with a trace from stdin")
+				.exit();
 		}
 
-		let reader = BinaryReader::<Access>::from_path(trace_path)
-			.expect("Invalid trace path.");
+		if args.validate_trace {
+			Args::command()
+				.error(ErrorKind::ArgumentConflict, "--validate-trace cannot be used with a trace from stdin")
+				.exit();
+		}
 
-		println!("\nProcessing {} accesses", fmt::number(reader.size() / Access::chunk_size() as u64));
+		if args.verify_checksum {
+			Args::command()
+				.error(ErrorKind::ArgumentConflict, "--verify-checksum cannot be used with a trace from stdin")
+				.exit();
+		}
 
-		let mut progress = Progress::new(reader.size())
-			.with_tag(Tag::Tps)
-			.with_tag(Tag::Eta)
-			.with_tag(Tag::Time);
+		if args.repeat > 1 {
+			Args::command()
+				.error(ErrorKind::ArgumentConflict, "--repeat cannot be used with a trace from stdin")
+				.exit();
+		}
+	}
 
-		let mut prev_access_timestamp: Option<u64> = None;
+	// the format named by the trace's header takes precedence over
+	// --trace-format, which only applies to legacy traces without one. The
+	// header of a trace from stdin is only read once the trace is opened
+	if !args.trace_path.is_empty() && !args.trace_path.iter().any(|path| is_stdin(path)) {
+		match trace::read_format(&args.trace_path) {
+			Ok(Some(format)) => set_trace_format(&mut args.trace_format, format),
+			Ok(None) => {},
 
-		for mut access in reader {
-			if args.native_time {
-				let prev_timestamp = prev_access_timestamp.unwrap_or(access.timestamp);
+			Err(err) => {
+				error!("Invalid trace: {err}");
+				process::exit(1);
+			},
+		}
+	}
 
-				if prev_timestamp > access.timestamp {
-					panic!("Invalid timestamp order.");
-				}
+	if args.validate_trace {
+		for trace_path in &args.trace_path {
+			info!("Validating trace {}", trace_path.display());
 
-				let sleep_duration = Duration::from_millis(access.timestamp - prev_timestamp);
-				spin_sleep::sleep(sleep_duration);
+			match trace::validate(trace_path, args.trace_format) {
+				Ok(count) => info!("Trace is valid ({} accesses)", fmt::number(count)),
 
-				prev_access_timestamp = Some(access.timestamp);
-			} else {
-				access.ttl = None;
+				Err(err) => {
+					error!("Invalid trace {}: {err}", trace_path.display());
+					process::exit(1);
+				},
 			}
+		}
+	}
+
+	if args.verify_checksum {
+		if args.trace_format != TraceFormat::V5 {
+			Args::command()
+				.error(ErrorKind::ArgumentConflict, "--verify-checksum requires --trace-format v5")
+				.exit();
+		}
+
+		for trace_path in &args.trace_path {
+			info!("Verifying checksums of trace {}", trace_path.display());
 
-			sender.send(ClientEvent::Access(access))
-				.expect("Could not send access to client.");
+			match trace::verify_checksums(trace_path, args.trace_format) {
+				Ok(count) => info!("Checksums are valid ({} accesses)", fmt::number(count)),
 
-			progress.tick(Access::chunk_size());
+				Err(err) => {
+					error!("Corrupted trace {}: {err}", trace_path.display());
+					process::exit(1);
+				},
+			}
 		}
 	}
 
-	drop(sender);
+	if let Some(output_dir) = args.output_dir.clone() {
+		fs::create_dir_all(&output_dir)
+			.expect("Could not create output directory.");
 
-	let mut stats = Stats::default();
+		set_output_paths(&mut args, &output_dir);
+	}
 
-	for task in tasks {
-		stats += task
-			.join()
-			.expect("Could not terminate client")
-			.expect("Error executing client requests");
+	let progress_format = args.progress_format.unwrap_or_else(|| {
+		match io::stdout().is_terminal() {
+			true => ProgressFormat::Bar,
+			false => ProgressFormat::Json,
+		}
+	});
+
+	if args.dry_run {
+		let reader = open_trace(&args.trace_path, args.trace_format);
+		set_trace_format(&mut args.trace_format, reader.format());
+
+		let count = reader.len();
+
+		let mut progress = match (count, reader.size()) {
+			(Some(count), _) => BenchmarkProgress::new(count, progress_format),
+			(None, Some(size)) => BenchmarkProgress::new(size, progress_format),
+			(None, None) => BenchmarkProgress::hidden(),
+		};
+
+		let mut trace_stats = TraceStats::default();
+
+		for access in reader {
+			trace_stats.push(&access);
+			match count {
+				Some(_) => progress.tick(1),
+				None => progress.tick(args.trace_format.record_size(&access) as u64),
+			}
+		}
+
+		progress.stop();
+		trace_stats.print();
+
+		return;
 	}
 
-	stats.print_ping_stats();
-	stats.print_get_stats();
-	stats.print_set_stats();
+	// a bad address fails once here rather than once for every client
+	let socket_addrs = match (args.host.trim_matches(['[', ']']), args.port).to_socket_addrs() {
+		Ok(socket_addrs) => socket_addrs.collect::<Vec<_>>(),
 
-	if args.output_csv.is_some() || args.output_plot.is_some() {
-		println!();
+		Err(err) => Args::command()
+			.error(ErrorKind::InvalidValue, format!("could not resolve {}:{}: {err}", args.host, args.port))
+			.exit(),
+	};
+
+	let is_local = socket_addrs
+		.iter()
+		.all(|socket_addr| socket_addr.ip().is_loopback());
+
+	// wiping a remote cache is only done once it has been confirmed
+	if !args.no_wipe && !is_local && !args.confirm_wipe && !confirm_wipe(&args.host, args.port) {
+		Args::command()
+			.error(ErrorKind::MissingRequiredArgument, format!("wiping the cache at {}:{} requires --confirm-wipe or --no-wipe", args.host, args.port))
+			.exit();
 	}
 
-	if let Some(path) = &args.output_csv {
-		stats.save_latency_percentiles(path)
-			.expect("Could not save latency percentiles.");
+	let paper_addr = get_paper_addr(&args.host, args.port);
+	let paper_addr = Arc::new(paper_addr);
+
+	// under key affinity each client gets its own channel so that every
+	// access to a key is handled by the same client
+	let (channel_count, default_capacity) = match args.key_affinity {
+		true => (args.clients, 1),
+		false => (1, args.clients),
+	};
+
+	let channel_capacity = args.channel_capacity.unwrap_or(default_capacity);
+
+	// under an open load model the channels are unbounded so that events
+	// are never held back by clients which have fallen behind
+	let (senders, receivers): (Vec<_>, Vec<_>) = (0..channel_count)
+		.map(|_| match args.load_model {
+			LoadModel::Open => unbounded::<(ClientEvent, Instant)>(),
+			LoadModel::Closed => bounded::<(ClientEvent, Instant)>(channel_capacity as usize),
+		})
+		.unzip();
+
+	let mut dispatcher = Dispatcher::new(senders);
+
+	let client_types = get_client_types(&args);
+
+	if args.client_mix.is_empty() {
+		info!("Client type: {}", args.client_type);
+	} else {
+		let mix = args.client_mix
+			.iter()
+			.map(|(client_type, _)| {
+				let count = client_types
+					.iter()
+					.filter(|other| *other == client_type)
+					.count();
 
-		println!("Saved CSV to <{}>.", path.to_str().unwrap_or(""));
+				format!("{count} {client_type}")
+			})
+			.collect::<Vec<_>>();
+
+		info!("Client mix: {}", mix.join(", "));
 	}
 
-	if let Some(path) = &args.output_plot {
-		stats.save_latency_plot(path)
-			.expect("Could not save latency plot.");
+	info!("Load model: {}", args.load_model);
 
-		println!("Saved plot to <{}>.", path.to_str().unwrap_or(""));
+	if args.channel_capacity.is_some() && args.load_model == LoadModel::Open {
+		warn!("--channel-capacity is ignored under the open load model, whose channels are unbounded");
 	}
-}
 
-fn get_trace_timespan<P>(path: P) -> io::Result<u64>
-where
-	P: AsRef<Path>,
-{
-	let mut reader = BinaryReader::<Access>::from_path(path)?;
-	let first_access = reader.read_chunk()?;
+	if args.key_affinity {
+		info!("Routing accesses to clients by key");
+	}
+
+	let auth = resolve_auth(&args);
+
+	if !args.no_wipe {
+		info!("Wiping cache");
+
+		client::wipe(&paper_addr, auth.as_deref())
+			.expect("Could not wipe cache.");
+	}
+
+	let error_policy = match args.abort_on_error || args.max_errors.is_some() {
+		true => ErrorPolicy::Abort,
+		false => ErrorPolicy::Continue,
+	};
+
+	let live_metrics = args.metrics_addr.map(|metrics_addr| {
+		let live_metrics = Arc::new(LiveMetrics::default());
+
+		metrics::serve(metrics_addr, live_metrics.clone())
+			.expect("Could not start metrics server.");
+
+		info!("Serving metrics on http://{metrics_addr}/metrics");
+
+		live_metrics
+	});
+
+	let timer = args.timer.timer();
+
+	let warmup_monitor = args.warmup_until_stable
+		.map(|threshold| Arc::new(WarmupMonitor::new(threshold)));
+
+	let raw_writer = args.output_raw.as_ref().map(|path| {
+		RawWriter::spawn(path)
+			.expect("Could not open raw output.")
+	});
+
+	// stored stats are only counted when their memory is limited, so that
+	// requests do not contend on the shared count otherwise
+	let stats_memory = args.max_stats_memory
+		.map(|max| Arc::new(StatsMemory::new(max * 1024 * 1024)));
+
+	let (stop_memory_log, memory_log_stopped) = bounded::<()>(0);
+
+	// the memory is logged periodically until the benchmark finishes as a
+	// warning sign before reaching the limit
+	let memory_logger = stats_memory.clone().map(|stats_memory| {
+		thread::spawn(move || {
+			while let Err(RecvTimeoutError::Timeout) = memory_log_stopped.recv_timeout(STATS_MEMORY_LOG_INTERVAL) {
+				info!("Stored stats use {}", fmt::memory(stats_memory.bytes(), Some(2)));
+			}
+		})
+	});
 
-	reader.seek(SeekFrom::End(-(Access::chunk_size() as i64)))?;
-	let last_access = reader.read_chunk()?;
+	// the trace is read once up front to find its most frequent keys
+	let hot_keys = args.hot_keys.map(|count| {
+		info!("Finding the {} hottest keys", fmt::number(count));
 
-	if last_access.timestamp < first_access.timestamp {
-		panic!("Invalid timestamp order.");
+		let hot_keys = trace::get_hot_keys(&args.trace_path, args.trace_format, count as usize)
+			.expect("Invalid trace path.");
+
+		// the clients prefix each key before classifying it
+		let hot_keys = match &args.key_prefix {
+			Some(key_prefix) => hot_keys
+				.into_iter()
+				.map(|key| format!("{key_prefix}{key}"))
+				.collect(),
+
+			None => hot_keys,
+		};
+
+		if let Some(stats_memory) = &stats_memory {
+			for key in &hot_keys {
+				stats_memory.store_key(key, 0);
+			}
+		}
+
+		Arc::new(hot_keys)
+	});
+
+	info!(
+		"Initializing {} client(s) with {} connection(s) each",
+		args.clients,
+		args.connection_pool_size,
+	);
+
+	// raised once every event has been sent, after which the clients
+	// record the requests draining their channels separately
+	let is_draining = Arc::new(AtomicBool::new(false));
+
+	let clients = client_types
+		.iter()
+		.enumerate()
+		.map(|(index, client_type)| {
+			let paper_addr = paper_addr.clone();
+			let receiver = receivers[index % receivers.len()].clone();
+
+			BenchmarkClient::new(&paper_addr, auth.clone(), receiver, args.connection_pool_size as usize)
+				.expect("Could not create client.")
+				.with_client_type(*client_type)
+				.with_backing_store_latency(Duration::from_micros(args.backing_store_latency))
+				.with_max_errors(args.max_errors.unwrap_or(0))
+				.with_error_policy(error_policy)
+				.with_max_reconnect_attempts(args.max_reconnect_attempts)
+				.with_value_size(args.value_size)
+				.with_idle_timeout(args.client_idle_timeout.map(Duration::from_secs))
+				.with_populate_ttl(args.populate_ttl.map(|ttl| Duration::from_secs(ttl.into())))
+				.with_key_prefix(args.key_prefix.clone())
+				.with_load_model(args.load_model)
+				.with_sample_rate(args.sample_rate)
+				.with_streaming_percentiles(args.streaming_percentiles)
+				.with_live_metrics(live_metrics.clone())
+				.with_hot_keys(hot_keys.clone())
+				.with_stats_memory(stats_memory.clone())
+				.with_raw_output(raw_writer.as_ref().map(RawWriter::output))
+				.with_timer(timer.clone())
+				.with_warmup_monitor(warmup_monitor.clone())
+				.with_is_draining(is_draining.clone())
+				.with_slow_threshold(args.op_slow_threshold.map(Duration::from_micros))
+				.with_verify_values(args.verify_values)
+				// each client derives its own seed so their values differ
+				.with_seed(args.seed.map(|seed| seed.wrapping_add(index as u64)))
+				.with_write_back(
+					args.write_back_batch_size as usize,
+					Duration::from_millis(args.write_back_interval),
+				)
+		})
+		.collect::<Vec<BenchmarkClient>>();
+
+	let connect_latencies = clients
+		.iter()
+		.flat_map(|client| client.connect_latencies().to_vec())
+		.collect::<Vec<_>>();
+
+	let auth_latencies = clients
+		.iter()
+		.flat_map(|client| client.auth_latencies().to_vec())
+		.collect::<Vec<_>>();
+
+	stats::print_setup_stats(&connect_latencies, &auth_latencies);
+
+	let tasks = clients
+		.into_iter()
+		.map(|mut client| thread::spawn(move || client.run()))
+		.collect::<Vec<_>>();
+
+	let duration = args.duration.map(Duration::from_secs);
+	let deadline = duration.map(|duration| Instant::now() + duration);
+
+	if let Some(duration) = duration {
+		info!("Running for at most {}", fmt::timespan(duration.as_millis() as u64));
+	}
+
+	let mut pacer = args.qps.map(Pacer::new);
+
+	if let Some(qps) = args.qps {
+		info!("Pacing requests to {} per second", fmt::number(qps));
 	}
 
-	Ok(last_access.timestamp - first_access.timestamp)
+	let is_interrupted = Arc::new(AtomicBool::new(false));
+
+	{
+		let is_interrupted = is_interrupted.clone();
+
+		ctrlc::set_handler(move || {
+			if is_interrupted.swap(true, Ordering::Relaxed) {
+				// a second interrupt skips waiting for the clients
+				process::exit(130);
+			}
+
+			warn!("Interrupted, waiting for clients to finish.");
+		}).expect("Could not set interrupt handler.");
+	}
+
+	let should_stop = || {
+		is_interrupted.load(Ordering::Relaxed)
+			|| stats_memory.as_deref().is_some_and(StatsMemory::is_exceeded)
+			|| deadline.is_some_and(|deadline| Instant::now() >= deadline)
+	};
+
+	if ping_count > 0 {
+		if ping_warmup > 0 {
+			match warmup_monitor {
+				Some(_) => info!("Warming up with up to {} pings until the p99 is stable", fmt::number(ping_warmup)),
+				None => info!("Warming up with {} pings", fmt::number(ping_warmup)),
+			}
+
+			let mut progress = BenchmarkProgress::new(ping_warmup, progress_format);
+			let mut warmup_count = 0;
+
+			for _ in 0..ping_warmup {
+				if should_stop() || is_warmup_stable(warmup_monitor.as_deref()) {
+					break;
+				}
+
+				if let Some(pacer) = &mut pacer {
+					pacer.wait();
+				}
+
+				dispatcher.send(ClientEvent::WarmupPing)
+					.expect("Could not send ping to client.");
+
+				warmup_count += 1;
+				progress.tick(1);
+			}
+
+			progress.stop();
+
+			if let Some(warmup_monitor) = &warmup_monitor {
+				log_stable_warmup(warmup_monitor, warmup_count, "pings");
+			}
+		}
+
+		info!("Performing {} pings", fmt::number(ping_count));
+
+		let mut progress = match deadline {
+			Some(deadline) => BenchmarkProgress::until(deadline, progress_format),
+			None => BenchmarkProgress::new(ping_count, progress_format),
+		};
+
+		for _ in 0..ping_count {
+			if should_stop() {
+				break;
+			}
+
+			if let Some(pacer) = &mut pacer {
+				pacer.wait();
+			}
+
+			dispatcher.send(ClientEvent::Ping)
+				.expect("Could not send ping to client.");
+
+			progress.tick(1);
+		}
+
+		progress.stop();
+	}
+
+	// set when an access goes back in time by more than --allow-skew, in
+	// which case the run stops early and fails
+	let mut is_skew_exceeded = false;
+
+	if has_accesses {
+		let mut timespan = 0;
+
+		if args.native_time {
+			timespan = match trace::get_timespan(&args.trace_path, args.trace_format, args.allow_skew) {
+				Ok(timespan) => timespan,
+
+				Err(err) => {
+					error!("Invalid trace: {err}");
+					process::exit(1);
+				},
+			};
+
+			info!("Using native access time");
+			info!("Total trace timestamp: {}", fmt::timespan(timespan));
+		}
+
+		let reader = (!args.synthetic).then(|| open_trace(&args.trace_path, args.trace_format));
+
+		if let Some(reader) = &reader {
+			set_trace_format(&mut args.trace_format, reader.format());
+		}
+
+		let count = match &reader {
+			Some(reader) => reader.len().map(|count| count * args.repeat),
+			None => Some(args.requests),
+		};
+
+		let count = count.map(|count| match args.max_requests {
+			Some(max_requests) => count.min(access_warmup + max_requests),
+			None => count,
+		});
+
+		let size = reader
+			.as_ref()
+			.and_then(TraceReader::size)
+			.map(|size| size * args.repeat);
+
+		match (count, size) {
+			(Some(count), _) => info!("Processing {} accesses", fmt::number(count)),
+			(None, Some(size)) => info!("Processing {} of accesses", fmt::memory(size, Some(2))),
+			(None, None) if args.trace_path.iter().any(|path| is_stdin(path)) => info!("Processing accesses from stdin"),
+			(None, None) => info!("Processing accesses from a compressed trace"),
+		}
+
+		if args.synthetic {
+			info!(
+				"Generating {:.1}% GETs over {} keys with a {} key distribution",
+				args.read_ratio * 100.0,
+				fmt::number(args.keys),
+				get_value_name(&args.key_dist),
+			);
+		}
+
+		if args.repeat > 1 {
+			info!("Replaying the trace {} times", fmt::number(args.repeat));
+		}
+
+		if let Some(max_requests) = args.max_requests {
+			info!("Stopping after {} accesses", fmt::number(max_requests));
+		}
+
+		if let Some(only) = &args.only {
+			info!("Replaying only {only} accesses");
+		}
+
+		if args.shuffle {
+			match reader.as_ref().and_then(TraceReader::size) {
+				Some(size) => info!("Shuffling the accesses, which loads {} into memory", fmt::memory(size, Some(2))),
+				None => info!("Shuffling the accesses, which loads the whole trace into memory"),
+			}
+		}
+
+		if access_warmup > 0 {
+			// the access phase is warmed up again from scratch since its
+			// latencies are not comparable to those of the pings
+			if let Some(warmup_monitor) = &warmup_monitor {
+				warmup_monitor.reset();
+			}
+
+			match warmup_monitor {
+				Some(_) => info!("Using up to the first {} accesses as warmup until the p99 is stable", fmt::number(access_warmup)),
+				None => info!("Using the first {} accesses as warmup", fmt::number(access_warmup)),
+			}
+		}
+
+		// progress is counted in accesses when the number of accesses is
+		// known up front, and in bytes otherwise
+		let mut progress = match (deadline, count, size) {
+			(Some(deadline), _, _) => BenchmarkProgress::until(deadline, progress_format),
+			(None, Some(count), _) => BenchmarkProgress::new(count, progress_format),
+			(None, None, Some(size)) => BenchmarkProgress::new(size, progress_format),
+			(None, None, None) => BenchmarkProgress::hidden(),
+		};
+
+		let mut shuffle_rng = args.shuffle.then(|| match args.seed {
+			Some(seed) => StdRng::seed_from_u64(seed),
+			None => StdRng::from_os_rng(),
+		});
+
+		// each repetition reopens the trace only once the previous one
+		// has been fully processed
+		let accesses: Box<dyn Iterator<Item = (u64, Access)>> = match reader {
+			Some(reader) => Box::new(iter::once(reader)
+				.chain((1..args.repeat).map(|_| open_trace(&args.trace_path, args.trace_format)))
+				.zip(0..)
+				.flat_map(move |(reader, repetition)| {
+					let accesses: Box<dyn Iterator<Item = Access>> = match &mut shuffle_rng {
+						Some(rng) => {
+							let mut accesses = reader.collect::<Vec<_>>();
+							accesses.shuffle(rng);
+
+							Box::new(accesses.into_iter())
+						},
+
+						None => Box::new(reader),
+					};
+
+					accesses.map(move |access| (repetition, access))
+				})),
+
+			None => {
+				let value_size = args.value_size.unwrap_or(SYNTHETIC_VALUE_SIZE);
+
+				let key_distribution = get_key_distribution(
+					args.key_dist,
+					args.keys,
+					args.zipf_skew,
+					args.hot_set_keys,
+					args.hot_set_traffic,
+				);
+
+				let workload = SyntheticWorkload::new(args.keys, args.read_ratio, value_size, args.requests)
+					.with_key_distribution(key_distribution)
+					.with_seed(args.seed);
+
+				Box::new(workload.map(|access| (0, access)))
+			},
+		};
+
+		let mut request_count = 0;
+		let mut prev_access_timestamp: Option<u64> = None;
+
+		// native-time accesses are paced from a dedicated thread which
+		// hands the dispatcher back once the trace has been sent
+		let scheduler = args.native_time.then(|| {
+			let is_interrupted = is_interrupted.clone();
+			let stats_memory = stats_memory.clone();
+
+			Scheduler::spawn(dispatcher.clone(), args.correct_omission, args.time_scale, move || {
+				is_interrupted.load(Ordering::Relaxed)
+					|| stats_memory.as_deref().is_some_and(StatsMemory::is_exceeded)
+					|| deadline.is_some_and(|deadline| Instant::now() >= deadline)
+			})
+		});
+
+		let mut warmup_count = 0;
+		let mut is_warming_up = access_warmup > 0;
+
+		// the gaps in milliseconds between consecutive accesses
+		let mut inter_arrival_times = LatencyDigest::default();
+
+		// how far in milliseconds each tolerated out-of-order access went
+		// back in time
+		let mut skews = LatencyDigest::default();
+
+		for (repetition, mut access) in accesses {
+			if should_stop() {
+				break;
+			}
+
+			let progress_value = match count {
+				Some(_) => 1,
+				None => args.trace_format.record_size(&access) as u64,
+			};
+
+			// filtered accesses still count towards the progress since the
+			// command mix of the trace is not known up front
+			if args.only.as_ref().is_some_and(|only| *only != access.command) {
+				progress.tick(progress_value);
+				continue;
+			}
+
+			if args.native_time {
+				// offset each repetition by the trace's timespan so the
+				// accesses stay evenly paced across repetitions
+				access.timestamp += repetition * timespan;
+
+				if let Some(ttl_unit) = args.ttl_unit {
+					access.ttl = access.ttl.map(|ttl| ttl_unit.reinterpret(ttl, args.trace_format.ttl_unit()));
+				}
+
+				let prev_timestamp = prev_access_timestamp.unwrap_or(access.timestamp);
+
+				if prev_timestamp > access.timestamp {
+					let skew = prev_timestamp - access.timestamp;
+
+					if skew > args.allow_skew {
+						error!("Stopped early as an access goes back {skew}ms, over --allow-skew");
+						is_skew_exceeded = true;
+
+						break;
+					}
+
+					// small backward jumps are sent without waiting
+					access.timestamp = prev_timestamp;
+					skews.push(skew as f64);
+				}
+
+				if prev_access_timestamp.is_some() {
+					inter_arrival_times.push((access.timestamp - prev_timestamp) as f64);
+				}
+
+				prev_access_timestamp = Some(access.timestamp);
+			} else {
+				access.ttl = None;
+			}
+
+			if let Some(pacer) = &mut pacer {
+				pacer.wait();
+			}
+
+			if is_warming_up
+				&& (warmup_count >= access_warmup || is_warmup_stable(warmup_monitor.as_deref()))
+			{
+				is_warming_up = false;
+
+				if let Some(warmup_monitor) = &warmup_monitor {
+					log_stable_warmup(warmup_monitor, warmup_count, "accesses");
+				}
+			}
+
+			let is_warmup = is_warming_up;
+
+			match is_warmup {
+				true => warmup_count += 1,
+				false => request_count += 1,
+			}
+
+			match &scheduler {
+				Some(scheduler) => {
+					// the scheduler only hangs up once it has stopped
+					if scheduler.schedule(access, is_warmup).is_err() {
+						break;
+					}
+				},
+
+				None => {
+					let event = match is_warmup {
+						true => ClientEvent::Warmup(access),
+						false => ClientEvent::Access(access, None),
+					};
+
+					dispatcher.send(event)
+						.expect("Could not send access to client.");
+				},
+			}
+
+			progress.tick(progress_value);
+
+			if args.max_requests.is_some_and(|max_requests| request_count >= max_requests) {
+				break;
+			}
+		}
+
+		progress.stop();
+
+		if let Some(scheduler) = scheduler {
+			let schedule_stats;
+			(dispatcher, schedule_stats) = scheduler.join();
+
+			stats::print_inter_arrival_stats(inter_arrival_times);
+			stats::print_skew_stats(skews);
+			stats::print_scheduling_stats(schedule_stats, args.time_scale);
+		}
+	}
+
+	// any events still queued once the trace has been sent are drained
+	// by the clients after this point
+	is_draining.store(true, Ordering::Relaxed);
+
+	if let Some(stats_memory) = &stats_memory
+		&& stats_memory.is_exceeded()
+	{
+		error!(
+			"Stopped early as the stored stats use {}, over --max-stats-memory",
+			fmt::memory(stats_memory.bytes(), Some(2)),
+		);
+	}
+
+	info!("Waiting for clients to finish");
+
+	let sends = dispatcher.sends();
+	let stalled_sends = dispatcher.stalled_sends();
+	let stall_time = dispatcher.stall_time();
+
+	drop(dispatcher);
+
+	let mut failed_clients = 0;
+	let mut drain_stats = Stats::default();
+
+	// a client which aborted is reported rather than discarding the stats
+	// of every other client
+	let (client_stats, client_lifetimes): (Vec<Stats>, Vec<_>) = tasks
+		.into_iter()
+		.enumerate()
+		.map(|(index, task)| {
+			let result = task
+				.join()
+				.expect("Could not terminate client");
+
+			match result {
+				Ok((mut stats, client_drain_stats, start, end)) => {
+					if !args.exclude_drain {
+						stats += client_drain_stats.clone();
+					}
+
+					drain_stats += client_drain_stats;
+
+					(stats, Some((start, end)))
+				},
+
+				Err(err) => {
+					error!("Client {index} failed: {err}");
+					failed_clients += 1;
+
+					(Stats::default(), None)
+				},
+			}
+		})
+		.unzip();
+
+	let client_lifetimes = client_lifetimes
+		.into_iter()
+		.flatten()
+		.collect::<Vec<_>>();
+
+	if failed_clients > 0 {
+		error!("{failed_clients} of {} clients failed, so their requests are not reported", args.clients);
+	}
+
+	drop(stop_memory_log);
+
+	if let Some(memory_logger) = memory_logger {
+		memory_logger
+			.join()
+			.expect("Could not terminate memory logger.");
+	}
+
+	if let Some(raw_writer) = raw_writer {
+		let records = raw_writer.finish()
+			.expect("Could not save raw output.");
+
+		let path = args.output_raw.as_deref().and_then(Path::to_str).unwrap_or("");
+		info!("Saved {records} raw request records to <{path}>.");
+	}
+
+	let percentiles = args.percentiles
+		.as_deref()
+		.unwrap_or(DEFAULT_PERCENTILES);
+
+	if args.per_client_stats {
+		println!("\n*** Per-client stats ***");
+
+		for (index, stats) in client_stats.iter().enumerate() {
+			stats.print_brief(&format!("Client {index}"), percentiles);
+		}
+	}
+
+	if args.client_mix.len() > 1 {
+		println!("\n*** Per-type stats ***");
+
+		for (client_type, _) in &args.client_mix {
+			let mut type_stats = Stats::default();
+
+			for (stats, _) in client_stats
+				.iter()
+				.zip(&client_types)
+				.filter(|(_, other)| *other == client_type)
+			{
+				type_stats += stats.clone();
+			}
+
+			type_stats.print_brief(&format!("{client_type} clients"), percentiles);
+		}
+	}
+
+	let mut stats = Stats::default();
+
+	for client_stats in client_stats {
+		stats += client_stats;
+	}
+
+	stats.print_ping_stats(percentiles);
+	stats.print_get_stats(percentiles);
+	stats.print_popularity_stats(percentiles);
+	stats.print_set_stats(percentiles);
+	stats.print_ttl_stats();
+	stats.print_del_stats(percentiles);
+
+	if args.combined_table {
+		stats.print_combined_table();
+	}
+
+	stats.print_write_back_stats(percentiles);
+	stats.print_queue_stats(percentiles);
+	drain_stats.print_drain_stats(percentiles);
+	stats.print_command_mix();
+	stats.print_connection_stats();
+	stats::print_concurrency_stats(&client_lifetimes, args.clients);
+	stats::print_stall_stats(sends, stalled_sends, stall_time);
+	stats.print_error_stats();
+	stats.print_overall_stats();
+
+	if let Some(path) = &args.output_csv {
+		stats.save_latency_percentiles(path, args.percentiles.as_deref())
+			.expect("Could not save latency percentiles.");
+
+		info!("Saved CSV to <{}>.", path.to_str().unwrap_or(""));
+	}
+
+	if let Some(path) = &args.output_plot {
+		stats.save_latency_plot(path, args.plot_resolution)
+			.expect("Could not save latency plot.");
+
+		info!("Saved plot to <{}>.", path.to_str().unwrap_or(""));
+	}
+
+	if let Some(path) = &args.output_percentile_plot {
+		stats.save_percentile_plot(path, !args.linear_percentile_plot)
+			.expect("Could not save percentile plot.");
+
+		info!("Saved percentile plot to <{}>.", path.to_str().unwrap_or(""));
+	}
+
+	if let Some(path) = &args.output_cdf_plot {
+		stats.save_cdf_plot(path)
+			.expect("Could not save CDF plot.");
+
+		info!("Saved CDF plot to <{}>.", path.to_str().unwrap_or(""));
+	}
+
+	if let Some(path) = &args.output_histogram {
+		stats.save_histogram_csv(path, args.histogram_buckets_per_decade)
+			.expect("Could not save histogram.");
+
+		info!("Saved histogram to <{}>.", path.to_str().unwrap_or(""));
+	}
+
+	if let Some(path) = &args.output_json {
+		stats.save_summary_json(path)
+			.expect("Could not save JSON summary.");
+
+		info!("Saved JSON to <{}>.", path.to_str().unwrap_or(""));
+	}
+
+	if let Some(path) = &args.output_throughput {
+		stats.save_throughput_csv(path)
+			.expect("Could not save throughput.");
+
+		info!("Saved throughput CSV to <{}>.", path.to_str().unwrap_or(""));
+	}
+
+	if let Some(path) = &args.output_percentile_timeseries {
+		stats.save_latency_percentile_timeseries_csv(path)
+			.expect("Could not save percentile time series.");
+
+		info!("Saved percentile time series CSV to <{}>.", path.to_str().unwrap_or(""));
+	}
+
+	#[cfg(feature = "hdrhistogram")]
+	if let Some(path) = &args.output_hdr {
+		stats.save_hdr_histogram(path)
+			.expect("Could not save HDR histogram.");
+
+		info!("Saved HDR histogram to <{}>.", path.to_str().unwrap_or(""));
+	}
+
+	if let Some(path) = &args.append_summary {
+		let first_start = client_lifetimes.iter().map(|(start, _)| *start).min();
+		let last_end = client_lifetimes.iter().map(|(_, end)| *end).max();
+
+		let duration = first_start
+			.zip(last_end)
+			.map(|(first_start, last_end)| last_end.duration_since(first_start))
+			.unwrap_or_default();
+
+		let run_name = args.run_name
+			.as_deref()
+			.unwrap_or("run");
+
+		stats.append_summary_csv(path, run_name, &get_run_config(&args), duration)
+			.expect("Could not append run summary.");
+
+		info!("Appended run summary to <{}>.", path.to_str().unwrap_or(""));
+	}
+
+	let slos = [
+		(50.0, args.slo_p50),
+		(99.0, args.slo_p99),
+		(99.9, args.slo_p999),
+		(100.0, args.slo_max),
+	]
+		.into_iter()
+		.filter_map(|(percentile, limit)| limit.map(|limit| (percentile, limit)))
+		.collect::<Vec<_>>();
+
+	let mut is_pass = !stats_memory.as_deref().is_some_and(StatsMemory::is_exceeded)
+		&& failed_clients == 0
+		&& !is_skew_exceeded;
+
+	if !slos.is_empty() {
+		is_pass &= stats.check_slos(&slos);
+	}
+
+	if let Some(expected_hit_rate) = args.expect_hit_rate {
+		is_pass &= stats.check_hit_rate(expected_hit_rate, args.hit_rate_tolerance);
+	}
+
+	if !is_pass {
+		process::exit(1);
+	}
+}
+
+/// Returns the address of the server, bracketing IPv6 literals so that the
+/// port can be told apart from the address.
+fn get_paper_addr(host: &str, port: u16) -> String {
+	match host.parse::<Ipv6Addr>() {
+		Ok(_) => format!("paper://[{host}]:{port}"),
+		Err(_) => format!("paper://{host}:{port}"),
+	}
+}
+
+/// Asks whether to wipe the cache at the supplied address if stdin is a
+/// terminal. Returns true only if the wipe was confirmed.
+fn confirm_wipe(host: &str, port: u16) -> bool {
+	if !io::stdin().is_terminal() {
+		return false;
+	}
+
+	print!("Wipe the cache at {host}:{port}? [y/N] ");
+
+	if io::stdout().flush().is_err() {
+		return false;
+	}
+
+	let mut answer = String::new();
+
+	if io::stdin().read_line(&mut answer).is_err() {
+		return false;
+	}
+
+	matches!(answer.trim(), "y" | "Y" | "yes")
+}
+
+/// Returns the auth token from --auth, --auth-file, or the PAPER_AUTH
+/// environment variable, in that order of precedence.
+fn resolve_auth(args: &Args) -> Option<String> {
+	if let Some(auth) = &args.auth {
+		return Some(auth.clone());
+	}
+
+	if let Some(auth_file) = &args.auth_file {
+		let auth = fs::read_to_string(auth_file)
+			.expect("Could not read auth file.");
+
+		return Some(auth.trim_end().to_owned());
+	}
+
+	env::var("PAPER_AUTH").ok()
+}
+
+/// Returns a description of the run's configuration for the summary row.
+fn get_run_config(args: &Args) -> String {
+	let client_type = match args.client_mix.is_empty() {
+		true => get_value_name(&args.client_type),
+
+		false => args.client_mix
+			.iter()
+			.map(|(client_type, weight)| format!("{}:{weight}", get_value_name(client_type)))
+			.collect::<Vec<_>>()
+			.join("+"),
+	};
+
+	let mut config = vec![
+		format!("clients={}", args.clients),
+		format!("connection_pool_size={}", args.connection_pool_size),
+		format!("client_type={client_type}"),
+		format!("load_model={}", get_value_name(&args.load_model)),
+	];
+
+	if let Some(qps) = args.qps {
+		config.push(format!("qps={qps}"));
+	}
+
+	if let Some(channel_capacity) = args.channel_capacity {
+		config.push(format!("channel_capacity={channel_capacity}"));
+	}
+
+	if args.native_time {
+		config.push("native_time".to_owned());
+	}
+
+	for path in &args.trace_path {
+		config.push(format!("trace={}", path.to_str().unwrap_or("")));
+	}
+
+	config.join(" ")
+}
+
+fn generate_trace(args: &GenerateArgs) {
+	let key_distribution = get_key_distribution(
+		args.key_dist,
+		args.keys,
+		args.zipf_skew,
+		args.hot_set_keys,
+		args.hot_set_traffic,
+	);
+
+	let count = SyntheticWorkload::new(args.keys, args.read_ratio, args.value_size, args.requests)
+		.with_key_distribution(key_distribution)
+		.with_interval(args.interval)
+		.with_ttl(args.ttl.map(|ttl| Duration::from_secs(ttl.into())))
+		.with_seed(args.seed)
+		.save_trace(&args.output)
+		.expect("Could not write trace.");
+
+	info!("Saved {count} accesses to <{}>.", args.output.to_str().unwrap_or(""));
+}
+
+fn get_key_distribution(
+	key_dist: KeyDistributionType,
+	keys: u64,
+	zipf_skew: f64,
+	hot_set_keys: f64,
+	hot_set_traffic: f64,
+) -> KeyDistribution {
+	match key_dist {
+		KeyDistributionType::Uniform => KeyDistribution::Uniform,
+		KeyDistributionType::Zipf => KeyDistribution::zipf(keys, zipf_skew),
+		KeyDistributionType::HotSet => KeyDistribution::hot_set(keys, hot_set_keys, hot_set_traffic),
+	}
+}
+
+fn is_warmup_stable(warmup_monitor: Option<&WarmupMonitor>) -> bool {
+	warmup_monitor.is_some_and(WarmupMonitor::is_stable)
+}
+
+fn log_stable_warmup(warmup_monitor: &WarmupMonitor, warmup_count: u64, unit: &str) {
+	match warmup_monitor.is_stable() {
+		true => info!("Warmup stabilized after {} {unit}", fmt::number(warmup_count)),
+		false => warn!("Warmup did not stabilize within {} {unit}", fmt::number(warmup_count)),
+	}
+}
+
+fn get_ping_count(args: &Args) -> u64 {
+	if args.no_ping {
+		return 0;
+	}
+
+	match args.ping_count {
+		Some(ping_count) => ping_count,
+		None if args.trace_path.is_empty() && !args.synthetic => PING_TEST_COUNT,
+		None => 0,
+	}
+}
+
+fn get_value_name<T>(value: &T) -> String
+where
+	T: ValueEnum,
+{
+	value
+		.to_possible_value()
+		.map(|value| value.get_name().to_owned())
+		.unwrap_or_default()
+}
+
+/// Returns the type of each client. Under a client mix, the clients are
+/// apportioned to each type by weight, with any clients left over going to
+/// the types with the largest remainders.
+fn get_client_types(args: &Args) -> Vec<ClientType> {
+	if args.client_mix.is_empty() {
+		return vec![args.client_type; args.clients as usize];
+	}
+
+	let total_weight = args.client_mix
+		.iter()
+		.map(|(_, weight)| *weight as u64)
+		.sum::<u64>();
+
+	let mut counts = args.client_mix
+		.iter()
+		.map(|(_, weight)| args.clients as u64 * *weight as u64 / total_weight)
+		.collect::<Vec<_>>();
+
+	let mut remainders = args.client_mix
+		.iter()
+		.enumerate()
+		.map(|(index, (_, weight))| (index, args.clients as u64 * *weight as u64 % total_weight))
+		.collect::<Vec<_>>();
+
+	remainders.sort_by(|(_, a), (_, b)| b.cmp(a));
+
+	let leftover = args.clients as u64 - counts.iter().sum::<u64>();
+
+	for (index, _) in remainders.into_iter().take(leftover as usize) {
+		counts[index] += 1;
+	}
+
+	args.client_mix
+		.iter()
+		.zip(counts)
+		.flat_map(|((client_type, _), count)| iter::repeat_n(*client_type, count as usize))
+		.collect()
+}
+
+/// Sets the path of every output which was not given one to a file in the
+/// output directory prefixed by the run name.
+fn set_output_paths(args: &mut Args, output_dir: &Path) {
+	let run_name = args.run_name
+		.as_deref()
+		.unwrap_or("run");
+
+	let path = |suffix: &str| Some(output_dir.join(format!("{run_name}-{suffix}")));
+
+	args.output_csv = args.output_csv.take().or_else(|| path("percentiles.csv"));
+	args.output_percentile_plot = args.output_percentile_plot.take().or_else(|| path("percentiles.png"));
+	args.output_json = args.output_json.take().or_else(|| path("summary.json"));
+
+	// these outputs need every latency to be stored
+	if !args.streaming_percentiles {
+		args.output_plot = args.output_plot.take().or_else(|| path("latency.png"));
+		args.output_cdf_plot = args.output_cdf_plot.take().or_else(|| path("cdf.png"));
+		args.output_histogram = args.output_histogram.take().or_else(|| path("histogram.csv"));
+		args.output_throughput = args.output_throughput.take().or_else(|| path("throughput.csv"));
+		args.output_percentile_timeseries = args.output_percentile_timeseries.take().or_else(|| path("percentile-timeseries.csv"));
+
+		#[cfg(feature = "hdrhistogram")]
+		{
+			args.output_hdr = args.output_hdr.take().or_else(|| path("latency.hlog"));
+		}
+	}
+}
+
+fn open_trace(trace_paths: &[PathBuf], format: TraceFormat) -> TraceReader {
+	match trace_paths {
+		[trace_path] if is_stdin(trace_path) => TraceReader::from_stdin(format)
+			.expect("Invalid trace from stdin."),
+
+		trace_paths => TraceReader::from_paths(trace_paths, format)
+			.expect("Invalid trace path."),
+	}
+}
+
+fn set_trace_format(trace_format: &mut TraceFormat, header_format: TraceFormat) {
+	if header_format != *trace_format {
+		info!("Using trace format {header_format} from the trace header");
+	}
+
+	*trace_format = header_format;
+}
+
+fn is_stdin(path: &Path) -> bool {
+	path.as_os_str() == "-"
+}
+
+fn parse_percentile(value: &str) -> Result<f64, String> {
+	let percentile = value
+		.parse::<f64>()
+		.map_err(|_| format!("invalid percentile `{value}`"))?;
+
+	if percentile.is_nan() || percentile <= 0.0 || percentile > 100.0 {
+		return Err(format!("percentile `{value}` is not in (0, 100]"));
+	}
+
+	Ok(percentile)
+}
+
+fn parse_client_mix_entry(value: &str) -> Result<(ClientType, u32), String> {
+	let Some((client_type, weight)) = value.split_once('=') else {
+		return Err(format!("client mix entry `{value}` is not of the form TYPE=WEIGHT"));
+	};
+
+	let client_type = ClientType::from_str(client_type, true)?;
+
+	let weight = weight
+		.parse::<u32>()
+		.map_err(|_| format!("invalid client weight `{weight}`"))?;
+
+	if weight == 0 {
+		return Err(format!("client weight `{weight}` must be positive"));
+	}
+
+	Ok((client_type, weight))
+}
+
+fn parse_hit_rate(value: &str) -> Result<f64, String> {
+	let hit_rate = value
+		.parse::<f64>()
+		.map_err(|_| format!("invalid hit rate `{value}`"))?;
+
+	if !(0.0..=100.0).contains(&hit_rate) {
+		return Err(format!("hit rate `{value}` is not in [0, 100]"));
+	}
+
+	Ok(hit_rate)
+}
+
+fn parse_time_scale(value: &str) -> Result<f64, String> {
+	let time_scale = value
+		.parse::<f64>()
+		.map_err(|_| format!("invalid time scale `{value}`"))?;
+
+	if !time_scale.is_finite() || time_scale < 0.0 {
+		return Err(format!("time scale `{value}` is not a non-negative number"));
+	}
+
+	Ok(time_scale)
+}
+
+fn parse_fraction(value: &str) -> Result<f64, String> {
+	let fraction = value
+		.parse::<f64>()
+		.map_err(|_| format!("invalid fraction `{value}`"))?;
+
+	if !(0.0..=1.0).contains(&fraction) {
+		return Err(format!("fraction `{value}` is not in [0, 1]"));
+	}
+
+	Ok(fraction)
+}
+
+fn parse_zipf_skew(value: &str) -> Result<f64, String> {
+	let skew = value
+		.parse::<f64>()
+		.map_err(|_| format!("invalid Zipfian skew `{value}`"))?;
+
+	if !skew.is_finite() || skew < 0.0 {
+		return Err(format!("Zipfian skew `{value}` is not a non-negative number"));
+	}
+
+	Ok(skew)
+}
+
+fn parse_warmup_threshold(value: &str) -> Result<f64, String> {
+	let threshold = value
+		.parse::<f64>()
+		.map_err(|_| format!("invalid warmup threshold `{value}`"))?;
+
+	if !threshold.is_finite() || threshold < 0.0 {
+		return Err(format!("warmup threshold `{value}` is not a non-negative number"));
+	}
+
+	Ok(threshold)
+}
+
+fn parse_sample_rate(value: &str) -> Result<f64, String> {
+	let sample_rate = value
+		.parse::<f64>()
+		.map_err(|_| format!("invalid sample rate `{value}`"))?;
+
+	if sample_rate.is_nan() || sample_rate <= 0.0 || sample_rate > 1.0 {
+		return Err(format!("sample rate `{value}` is not in (0, 1]"));
+	}
+
+	Ok(sample_rate)
+}
+
+#[cfg(test)]
+mod tests {
+	use clap::Parser;
+
+	use crate::{
+		Args,
+		client::ClientType,
+		get_paper_addr,
+		get_client_types,
+		parse_percentile,
+		parse_client_mix_entry,
+		parse_hit_rate,
+		parse_time_scale,
+		parse_fraction,
+		parse_zipf_skew,
+		parse_warmup_threshold,
+		parse_sample_rate,
+	};
+
+	#[test]
+	fn client_type_parses_lookaside() {
+		let args = Args::try_parse_from(["paper-benchmark", "--client-type", "lookaside"])
+			.expect("Could not parse lookaside client type.");
+
+		assert_eq!(args.client_type, ClientType::Lookaside);
+	}
+
+	#[test]
+	fn client_type_parses_read_through() {
+		let args = Args::try_parse_from(["paper-benchmark", "--client-type", "read-through"])
+			.expect("Could not parse read-through client type.");
+
+		assert_eq!(args.client_type, ClientType::ReadThrough);
+	}
+
+	#[test]
+	fn zipf_parses_skew() {
+		let args = Args::try_parse_from(["paper-benchmark", "--synthetic", "--zipf", "0.9"])
+			.expect("Could not parse --zipf.");
+
+		assert_eq!(args.zipf, Some(0.9));
+	}
+
+	#[test]
+	fn zipf_conflicts_with_key_dist() {
+		let result = Args::try_parse_from(["paper-benchmark", "--synthetic", "--zipf", "0.9", "--key-dist", "uniform"]);
+		assert!(result.is_err());
+	}
+
+	#[test]
+	fn client_type_defaults_to_lookaside() {
+		let args = Args::try_parse_from(["paper-benchmark"])
+			.expect("Could not parse default client type.");
+
+		assert_eq!(args.client_type, ClientType::Lookaside);
+	}
+
+	#[test]
+	fn paper_addr_brackets_ipv6_literal() {
+		assert_eq!(get_paper_addr("::1", 3145), "paper://[::1]:3145");
+		assert_eq!(get_paper_addr("fe80::1:2", 80), "paper://[fe80::1:2]:80");
+	}
+
+	#[test]
+	fn paper_addr_keeps_bracketed_ipv6_literal() {
+		assert_eq!(get_paper_addr("[::1]", 3145), "paper://[::1]:3145");
+	}
+
+	#[test]
+	fn paper_addr_keeps_hostname() {
+		assert_eq!(get_paper_addr("cache.example.com", 3145), "paper://cache.example.com:3145");
+		assert_eq!(get_paper_addr("localhost", 65535), "paper://localhost:65535");
+	}
+
+	#[test]
+	fn paper_addr_keeps_ipv4_literal() {
+		assert_eq!(get_paper_addr("127.0.0.1", 3145), "paper://127.0.0.1:3145");
+	}
+
+	#[test]
+	fn client_types_without_mix_use_client_type() {
+		let client_types = get_mixed_client_types(&["--clients", "3", "--client-type", "write-back"]);
+		assert_eq!(client_types, [ClientType::WriteBack; 3]);
+	}
+
+	#[test]
+	fn client_mix_splits_exact_proportions() {
+		let client_types = get_mixed_client_types(&["--clients", "4", "--client-mix", "lookaside=3,read-through=1"]);
+
+		assert_eq!(client_types, [
+			ClientType::Lookaside,
+			ClientType::Lookaside,
+			ClientType::Lookaside,
+			ClientType::ReadThrough,
+		]);
+	}
+
+	#[test]
+	fn client_mix_gives_leftover_to_largest_remainder() {
+		// 4 * 1/3 leaves a remainder of 1 and 4 * 2/3 a remainder of 2
+		let client_types = get_mixed_client_types(&["--clients", "4", "--client-mix", "lookaside=1,read-through=2"]);
+
+		assert_eq!(client_types, [
+			ClientType::Lookaside,
+			ClientType::ReadThrough,
+			ClientType::ReadThrough,
+			ClientType::ReadThrough,
+		]);
+	}
+
+	#[test]
+	fn client_mix_breaks_remainder_ties_in_order() {
+		let client_types = get_mixed_client_types(&["--clients", "5", "--client-mix", "lookaside=1,read-through=1,write-through=1"]);
+
+		assert_eq!(client_types, [
+			ClientType::Lookaside,
+			ClientType::Lookaside,
+			ClientType::ReadThrough,
+			ClientType::ReadThrough,
+			ClientType::WriteThrough,
+		]);
+	}
+
+	#[test]
+	fn client_mix_keeps_client_count() {
+		for clients in 1..=32u32 {
+			let clients_arg = clients.to_string();
+			let client_types = get_mixed_client_types(&["--clients", &clients_arg, "--client-mix", "lookaside=7,read-through=3,write-back=5"]);
+
+			assert_eq!(client_types.len(), clients as usize);
+		}
+	}
+
+	#[test]
+	fn client_mix_entry_parses_weight() {
+		assert_eq!(parse_client_mix_entry("read-through=2"), Ok((ClientType::ReadThrough, 2)));
+	}
+
+	#[test]
+	fn client_mix_entry_rejects_invalid_entries() {
+		assert!(parse_client_mix_entry("lookaside").is_err());
+		assert!(parse_client_mix_entry("lookaside=0").is_err());
+		assert!(parse_client_mix_entry("lookaside=-1").is_err());
+		assert!(parse_client_mix_entry("unknown=1").is_err());
+	}
+
+	#[test]
+	fn percentile_parses_bounds() {
+		assert_eq!(parse_percentile("99.9"), Ok(99.9));
+		assert_eq!(parse_percentile("100"), Ok(100.0));
+
+		assert!(parse_percentile("0").is_err());
+		assert!(parse_percentile("100.1").is_err());
+		assert!(parse_percentile("NaN").is_err());
+		assert!(parse_percentile("p99").is_err());
+	}
+
+	#[test]
+	fn hit_rate_parses_bounds() {
+		assert_eq!(parse_hit_rate("0"), Ok(0.0));
+		assert_eq!(parse_hit_rate("100"), Ok(100.0));
+
+		assert!(parse_hit_rate("-1").is_err());
+		assert!(parse_hit_rate("100.1").is_err());
+		assert!(parse_hit_rate("NaN").is_err());
+	}
+
+	#[test]
+	fn time_scale_parses_bounds() {
+		assert_eq!(parse_time_scale("0"), Ok(0.0));
+		assert_eq!(parse_time_scale("2.5"), Ok(2.5));
+
+		assert!(parse_time_scale("-1").is_err());
+		assert!(parse_time_scale("inf").is_err());
+		assert!(parse_time_scale("NaN").is_err());
+	}
+
+	#[test]
+	fn fraction_parses_bounds() {
+		assert_eq!(parse_fraction("0"), Ok(0.0));
+		assert_eq!(parse_fraction("1"), Ok(1.0));
+
+		assert!(parse_fraction("-0.1").is_err());
+		assert!(parse_fraction("1.1").is_err());
+		assert!(parse_fraction("NaN").is_err());
+	}
+
+	#[test]
+	fn zipf_skew_parses_bounds() {
+		assert_eq!(parse_zipf_skew("0"), Ok(0.0));
+		assert_eq!(parse_zipf_skew("1.2"), Ok(1.2));
+
+		assert!(parse_zipf_skew("-0.1").is_err());
+		assert!(parse_zipf_skew("inf").is_err());
+		assert!(parse_zipf_skew("NaN").is_err());
+	}
+
+	#[test]
+	fn warmup_threshold_parses_bounds() {
+		assert_eq!(parse_warmup_threshold("0"), Ok(0.0));
+		assert_eq!(parse_warmup_threshold("5"), Ok(5.0));
+
+		assert!(parse_warmup_threshold("-1").is_err());
+		assert!(parse_warmup_threshold("inf").is_err());
+		assert!(parse_warmup_threshold("NaN").is_err());
+	}
+
+	#[test]
+	fn sample_rate_parses_bounds() {
+		assert_eq!(parse_sample_rate("0.01"), Ok(0.01));
+		assert_eq!(parse_sample_rate("1"), Ok(1.0));
+
+		assert!(parse_sample_rate("0").is_err());
+		assert!(parse_sample_rate("1.1").is_err());
+		assert!(parse_sample_rate("NaN").is_err());
+	}
+
+	fn get_mixed_client_types(args: &[&str]) -> Vec<ClientType> {
+		let args = Args::try_parse_from(["paper-benchmark"].iter().chain(args))
+			.expect("Could not parse client mix.");
+
+		get_client_types(&args)
+	}
 }
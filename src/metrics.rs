@@ -0,0 +1,206 @@
+/*
+ * Copyright (c) Kia Shakiba
+ *
+ * This source code is licensed under the GNU AGPLv3 license found in the
+ * LICENSE file in the root directory of this source tree.
+ */
+
+use std::{
+	thread,
+	fmt::Write as _,
+	net::{SocketAddr, TcpListener, TcpStream},
+	io::{self, BufRead, BufReader, Write},
+	sync::{
+		Arc,
+		atomic::{AtomicU64, Ordering},
+	},
+	time::{Instant, Duration},
+};
+
+// the upper bounds in microseconds of the latency histogram buckets
+const BUCKET_BOUNDS: [u64; 19] = [
+	10, 20, 50,
+	100, 200, 500,
+	1_000, 2_000, 5_000,
+	10_000, 20_000, 50_000,
+	100_000, 200_000, 500_000,
+	1_000_000, 2_000_000, 5_000_000,
+	10_000_000,
+];
+
+/// Counters shared by every client which are published while the
+/// benchmark runs.
+#[derive(Debug, Default)]
+pub struct LiveMetrics {
+	requests: AtomicU64,
+	errors: AtomicU64,
+
+	get_hits: AtomicU64,
+	get_misses: AtomicU64,
+
+	latency_sum: AtomicU64,
+
+	// the final bucket counts the latencies above the largest bound
+	buckets: [AtomicU64; BUCKET_BOUNDS.len() + 1],
+}
+
+impl LiveMetrics {
+	pub fn store_request(&self, latency: Duration) {
+		let latency = latency.as_micros() as u64;
+
+		let index = BUCKET_BOUNDS
+			.iter()
+			.position(|bound| latency <= *bound)
+			.unwrap_or(BUCKET_BOUNDS.len());
+
+		self.requests.fetch_add(1, Ordering::Relaxed);
+		self.latency_sum.fetch_add(latency, Ordering::Relaxed);
+		self.buckets[index].fetch_add(1, Ordering::Relaxed);
+	}
+
+	pub fn store_error(&self) {
+		self.errors.fetch_add(1, Ordering::Relaxed);
+	}
+
+	pub fn store_get_hit(&self) {
+		self.get_hits.fetch_add(1, Ordering::Relaxed);
+	}
+
+	pub fn store_get_miss(&self) {
+		self.get_misses.fetch_add(1, Ordering::Relaxed);
+	}
+
+	/// Renders the metrics in the Prometheus text format. The throughput
+	/// is measured since the supplied instant and request count.
+	fn render(&self, since: (Instant, u64)) -> String {
+		let requests = self.requests.load(Ordering::Relaxed);
+		let errors = self.errors.load(Ordering::Relaxed);
+		let get_hits = self.get_hits.load(Ordering::Relaxed);
+		let get_misses = self.get_misses.load(Ordering::Relaxed);
+		let latency_sum = self.latency_sum.load(Ordering::Relaxed);
+
+		let buckets = self.buckets
+			.iter()
+			.map(|bucket| bucket.load(Ordering::Relaxed))
+			.collect::<Vec<_>>();
+
+		let (since_instant, since_requests) = since;
+		let elapsed = since_instant.elapsed().as_secs_f64();
+
+		let ops_per_second = match elapsed > 0.0 {
+			true => requests.saturating_sub(since_requests) as f64 / elapsed,
+			false => 0.0,
+		};
+
+		let hit_rate = match get_hits + get_misses {
+			0 => 0.0,
+			gets => get_hits as f64 / gets as f64,
+		};
+
+		let mut output = String::new();
+
+		let _ = writeln!(output, "# TYPE paper_benchmark_requests_total counter");
+		let _ = writeln!(output, "paper_benchmark_requests_total {requests}");
+		let _ = writeln!(output, "# TYPE paper_benchmark_errors_total counter");
+		let _ = writeln!(output, "paper_benchmark_errors_total {errors}");
+		let _ = writeln!(output, "# TYPE paper_benchmark_ops_per_second gauge");
+		let _ = writeln!(output, "paper_benchmark_ops_per_second {ops_per_second}");
+		let _ = writeln!(output, "# TYPE paper_benchmark_hit_rate gauge");
+		let _ = writeln!(output, "paper_benchmark_hit_rate {hit_rate}");
+		let _ = writeln!(output, "# TYPE paper_benchmark_latency_p99_seconds gauge");
+		let _ = writeln!(output, "paper_benchmark_latency_p99_seconds {}", get_p99(&buckets, requests));
+		let _ = writeln!(output, "# TYPE paper_benchmark_request_duration_seconds histogram");
+
+		let mut cumulative = 0;
+
+		for (bound, count) in BUCKET_BOUNDS.iter().zip(&buckets) {
+			cumulative += count;
+
+			let _ = writeln!(
+				output,
+				"paper_benchmark_request_duration_seconds_bucket{{le=\"{}\"}} {cumulative}",
+				*bound as f64 / 1_000_000.0,
+			);
+		}
+
+		let _ = writeln!(output, "paper_benchmark_request_duration_seconds_bucket{{le=\"+Inf\"}} {requests}");
+		let _ = writeln!(output, "paper_benchmark_request_duration_seconds_sum {}", latency_sum as f64 / 1_000_000.0);
+		let _ = writeln!(output, "paper_benchmark_request_duration_seconds_count {requests}");
+
+		output
+	}
+}
+
+/// Serves the metrics at /metrics on the supplied address from a
+/// background thread.
+pub fn serve(addr: SocketAddr, metrics: Arc<LiveMetrics>) -> io::Result<()> {
+	let listener = TcpListener::bind(addr)?;
+
+	thread::spawn(move || {
+		// the throughput is measured between consecutive scrapes
+		let mut since = (Instant::now(), 0);
+
+		for stream in listener.incoming().flatten() {
+			let _ = respond(stream, &metrics, &mut since);
+		}
+	});
+
+	Ok(())
+}
+
+fn respond(
+	mut stream: TcpStream,
+	metrics: &LiveMetrics,
+	since: &mut (Instant, u64),
+) -> io::Result<()> {
+	let mut reader = BufReader::new(stream.try_clone()?);
+
+	let mut request_line = String::new();
+	reader.read_line(&mut request_line)?;
+
+	// the request headers are not needed
+	let mut header = String::new();
+
+	while reader.read_line(&mut header)? > 2 {
+		header.clear();
+	}
+
+	let path = request_line
+		.split_whitespace()
+		.nth(1)
+		.unwrap_or("");
+
+	if path != "/metrics" {
+		return stream.write_all(b"HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n");
+	}
+
+	let body = metrics.render(*since);
+	*since = (Instant::now(), metrics.requests.load(Ordering::Relaxed));
+
+	write!(
+		stream,
+		"HTTP/1.1 200 OK\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+		body.len(),
+	)
+}
+
+/// Returns the upper bound in seconds of the bucket containing the 99th
+/// percentile latency.
+fn get_p99(buckets: &[u64], requests: u64) -> f64 {
+	if requests == 0 {
+		return 0.0;
+	}
+
+	let target = (requests as f64 * 0.99).ceil() as u64;
+	let mut cumulative = 0;
+
+	for (bound, count) in BUCKET_BOUNDS.iter().zip(buckets) {
+		cumulative += count;
+
+		if cumulative >= target {
+			return *bound as f64 / 1_000_000.0;
+		}
+	}
+
+	f64::INFINITY
+}
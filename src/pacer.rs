@@ -0,0 +1,38 @@
+/*
+ * Copyright (c) Kia Shakiba
+ *
+ * This source code is licensed under the GNU AGPLv3 license found in the
+ * LICENSE file in the root directory of this source tree.
+ */
+
+use std::time::{Instant, Duration};
+
+/// Paces events to a fixed rate. Events are scheduled at fixed intervals
+/// from the first one so that a late event does not delay the rest.
+pub struct Pacer {
+	interval: Duration,
+	next: Option<Instant>,
+}
+
+impl Pacer {
+	pub fn new(rate: u32) -> Self {
+		assert!(rate > 0, "Rate cannot be zero.");
+
+		Pacer {
+			interval: Duration::from_secs(1) / rate,
+			next: None,
+		}
+	}
+
+	/// Blocks until the next event is due.
+	pub fn wait(&mut self) {
+		let now = Instant::now();
+		let next = self.next.unwrap_or(now);
+
+		if next > now {
+			spin_sleep::sleep(next - now);
+		}
+
+		self.next = Some(next + self.interval);
+	}
+}
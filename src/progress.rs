@@ -0,0 +1,215 @@
+/*
+ * Copyright (c) Kia Shakiba
+ *
+ * This source code is licensed under the GNU AGPLv3 license found in the
+ * LICENSE file in the root directory of this source tree.
+ */
+
+use std::{
+	fmt::{self, Display},
+	time::{Instant, Duration},
+};
+
+use clap::ValueEnum;
+use serde::Serialize;
+use kwik::progress::{Progress, Tag};
+
+// the interval between JSON progress reports
+const JSON_REPORT_INTERVAL: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Copy, Clone, ValueEnum)]
+pub enum ProgressFormat {
+	/// A progress bar drawn to the terminal.
+	Bar,
+
+	/// Periodic JSON objects written to stderr, one per line.
+	Json,
+}
+
+/// A progress bar which either tracks the number of processed events or,
+/// when the benchmark is limited by a duration, the elapsed time.
+pub struct BenchmarkProgress {
+	output: Option<Output>,
+	timer: Option<Timer>,
+}
+
+enum Output {
+	Bar(Box<Progress>),
+	Json(JsonProgress),
+}
+
+struct Timer {
+	start: Instant,
+
+	elapsed_ms: u64,
+	total_ms: u64,
+}
+
+struct JsonProgress {
+	start: Instant,
+	last_report: Instant,
+
+	processed: u64,
+	total: u64,
+}
+
+#[derive(Serialize)]
+struct JsonReport {
+	processed: u64,
+	total: u64,
+	tps: f64,
+	eta_secs: f64,
+}
+
+impl BenchmarkProgress {
+	pub fn new(total: u64, format: ProgressFormat) -> Self {
+		let output = match format {
+			ProgressFormat::Bar => {
+				let progress = Progress::new(total)
+					.with_tag(Tag::Tps)
+					.with_tag(Tag::Eta)
+					.with_tag(Tag::Time);
+
+				Output::Bar(Box::new(progress))
+			},
+
+			ProgressFormat::Json => Output::Json(JsonProgress::new(total)),
+		};
+
+		BenchmarkProgress {
+			output: Some(output),
+			timer: None,
+		}
+	}
+
+	/// Creates a progress bar which is not displayed, for when the total
+	/// amount of work is not known.
+	pub fn hidden() -> Self {
+		BenchmarkProgress {
+			output: None,
+			timer: None,
+		}
+	}
+
+	pub fn until(deadline: Instant, format: ProgressFormat) -> Self {
+		let start = Instant::now();
+
+		let total_ms = deadline
+			.saturating_duration_since(start)
+			.as_millis()
+			.max(1) as u64;
+
+		let output = match format {
+			ProgressFormat::Bar => {
+				let progress = Progress::new(total_ms)
+					.with_tag(Tag::Eta)
+					.with_tag(Tag::Time);
+
+				Output::Bar(Box::new(progress))
+			},
+
+			ProgressFormat::Json => Output::Json(JsonProgress::new(total_ms)),
+		};
+
+		let timer = Timer {
+			start,
+
+			elapsed_ms: 0,
+			total_ms,
+		};
+
+		BenchmarkProgress {
+			output: Some(output),
+			timer: Some(timer),
+		}
+	}
+
+	pub fn tick(&mut self, value: u64) {
+		let Some(output) = &mut self.output else {
+			return;
+		};
+
+		let Some(timer) = &mut self.timer else {
+			return output.tick(value);
+		};
+
+		let elapsed_ms = (timer.start.elapsed().as_millis() as u64).min(timer.total_ms);
+
+		if elapsed_ms > timer.elapsed_ms {
+			output.tick(elapsed_ms - timer.elapsed_ms);
+			timer.elapsed_ms = elapsed_ms;
+		}
+	}
+
+	pub fn stop(&mut self) {
+		match &mut self.output {
+			Some(Output::Bar(progress)) => progress.stop(),
+			Some(Output::Json(progress)) => progress.report(),
+			None => {},
+		}
+	}
+}
+
+impl Output {
+	fn tick(&mut self, value: u64) {
+		match self {
+			Output::Bar(progress) => progress.tick(value),
+			Output::Json(progress) => progress.tick(value),
+		}
+	}
+}
+
+impl JsonProgress {
+	fn new(total: u64) -> Self {
+		let now = Instant::now();
+
+		JsonProgress {
+			start: now,
+			last_report: now,
+
+			processed: 0,
+			total,
+		}
+	}
+
+	fn tick(&mut self, value: u64) {
+		self.processed += value;
+
+		if self.last_report.elapsed() >= JSON_REPORT_INTERVAL {
+			self.report();
+		}
+	}
+
+	fn report(&mut self) {
+		self.last_report = Instant::now();
+
+		let tps = self.processed as f64 / self.start.elapsed().as_secs_f64();
+
+		let eta_secs = match tps > 0.0 {
+			true => self.total.saturating_sub(self.processed) as f64 / tps,
+			false => 0.0,
+		};
+
+		let report = JsonReport {
+			processed: self.processed,
+			total: self.total,
+			tps,
+			eta_secs,
+		};
+
+		if let Ok(line) = serde_json::to_string(&report) {
+			eprintln!("{line}");
+		}
+	}
+}
+
+impl Display for ProgressFormat {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		let s = match self {
+			ProgressFormat::Bar => "bar",
+			ProgressFormat::Json => "json",
+		};
+
+		write!(f, "{s}")
+	}
+}
@@ -0,0 +1,147 @@
+/*
+ * Copyright (c) Kia Shakiba
+ *
+ * This source code is licensed under the GNU AGPLv3 license found in the
+ * LICENSE file in the root directory of this source tree.
+ */
+
+use std::{
+	io,
+	path::Path,
+	thread::{self, JoinHandle},
+	time::{Instant, Duration},
+};
+
+use crossbeam_channel::{Sender, bounded};
+
+use kwik::file::{
+	FileWriter,
+	csv::{CsvWriter, RowData, WriteRow},
+};
+
+use crate::{
+	access::Command,
+	stats::Stats,
+};
+
+// the number of records buffered before clients block on the writer
+const RAW_BUFFER: usize = 65_536;
+
+/// Streams a record of every completed request to a CSV file from a
+/// dedicated thread, so that the records are never held in memory.
+pub struct RawWriter {
+	output: RawOutput,
+	handle: JoinHandle<io::Result<u64>>,
+}
+
+/// The sending half of a raw writer, held by each client's stats.
+#[derive(Debug, Clone)]
+pub struct RawOutput {
+	sender: Sender<RawRecord>,
+	start: Instant,
+}
+
+struct RawRecord {
+	offset: Duration,
+	// pings have no command
+	command: Option<Command>,
+	latency: Duration,
+	size: u64,
+	hit: Option<bool>,
+}
+
+impl RawWriter {
+	pub fn spawn<P>(path: P) -> io::Result<Self>
+	where
+		P: AsRef<Path>,
+	{
+		let mut writer = CsvWriter::<RawRecord>::from_path(path)?
+			.with_headers(&["schema_version", "timestamp_offset_us", "operation", "latency_us", "size", "hit"])?;
+
+		let (sender, receiver) = bounded::<RawRecord>(RAW_BUFFER);
+
+		let handle = thread::spawn(move || {
+			let mut count = 0;
+
+			for record in receiver {
+				writer.write_row(&record)?;
+				count += 1;
+			}
+
+			writer.flush()?;
+
+			Ok(count)
+		});
+
+		let output = RawOutput {
+			sender,
+			start: Instant::now(),
+		};
+
+		let raw_writer = RawWriter {
+			output,
+			handle,
+		};
+
+		Ok(raw_writer)
+	}
+
+	pub fn output(&self) -> RawOutput {
+		self.output.clone()
+	}
+
+	/// Waits for every record to be written once all other outputs have
+	/// been dropped, returning the number of records written.
+	pub fn finish(self) -> io::Result<u64> {
+		drop(self.output);
+
+		self.handle
+			.join()
+			.expect("Could not terminate raw writer.")
+	}
+}
+
+impl RawOutput {
+	/// Sends a record of a request which started at `start_time` and has
+	/// just completed.
+	pub fn store(
+		&self,
+		command: Option<&Command>,
+		start_time: Instant,
+		latency: Duration,
+		size: u64,
+		hit: Option<bool>,
+	) {
+		let record = RawRecord {
+			offset: start_time.saturating_duration_since(self.start),
+			command: command.cloned(),
+			latency,
+			size,
+			hit,
+		};
+
+		// a failed writer is reported when it is finished
+		let _ = self.sender.send(record);
+	}
+}
+
+impl WriteRow for RawRecord {
+	fn as_row(&self, row: &mut RowData) -> io::Result<()> {
+		row.push(Stats::OUTPUT_SCHEMA_VERSION);
+		row.push(self.offset.as_micros());
+		match &self.command {
+			Some(command) => row.push(command),
+			None => row.push("PING"),
+		}
+
+		row.push(self.latency.as_micros());
+		row.push(self.size);
+
+		match self.hit {
+			Some(hit) => row.push(u8::from(hit)),
+			None => row.push(""),
+		}
+
+		Ok(())
+	}
+}
@@ -0,0 +1,155 @@
+/*
+ * Copyright (c) Kia Shakiba
+ *
+ * This source code is licensed under the GNU AGPLv3 license found in the
+ * LICENSE file in the root directory of this source tree.
+ */
+
+use std::{
+	thread::{self, JoinHandle},
+	time::{Instant, Duration},
+};
+
+use crossbeam_channel::{Sender, SendError, bounded};
+
+use crate::{
+	access::Access,
+	client::ClientEvent,
+	dispatcher::Dispatcher,
+	distribution::LatencyDigest,
+};
+
+// the number of accesses read ahead of the pacing thread
+const SCHEDULE_BUFFER: usize = 1024;
+
+/// Dispatches accesses at their native trace times from a dedicated thread
+/// so that reading the trace and rendering progress do not delay them.
+pub struct Scheduler {
+	sender: Sender<(Access, bool)>,
+	handle: JoinHandle<(Dispatcher, ScheduleStats)>,
+}
+
+/// How closely the dispatched accesses kept to their schedule.
+pub struct ScheduleStats {
+	/// The delays in microseconds between when each access was scheduled
+	/// and when it was dispatched.
+	pub scheduling_errors: LatencyDigest,
+
+	/// The trace time covered by the dispatched accesses.
+	pub trace_time: Duration,
+
+	/// The wall time taken to dispatch the accesses.
+	pub wall_time: Duration,
+}
+
+impl Scheduler {
+	/// Spawns the pacing thread. With `correct_omission`, accesses are
+	/// scheduled against the start of the trace so that falling behind does
+	/// not push back later accesses, otherwise each access is scheduled
+	/// relative to the one before it. The trace is replayed `time_scale`
+	/// times faster than native time, or as fast as possible if zero.
+	pub fn spawn<F>(
+		mut dispatcher: Dispatcher,
+		correct_omission: bool,
+		time_scale: f64,
+		should_stop: F,
+	) -> Self
+	where
+		F: Fn() -> bool + Send + 'static,
+	{
+		let (sender, receiver) = bounded::<(Access, bool)>(SCHEDULE_BUFFER);
+
+		let handle = thread::spawn(move || {
+			let mut schedule_start: Option<(Instant, u64)> = None;
+			let mut prev_timestamp: Option<u64> = None;
+
+			let mut scheduling_errors = LatencyDigest::default();
+
+			// the instant and timestamp of the first and last dispatched accesses
+			let mut first_dispatch: Option<(Instant, u64)> = None;
+			let mut last_dispatch: Option<(Instant, u64)> = None;
+
+			let scale = |trace_time: u64| match time_scale > 0.0 {
+				true => Duration::from_millis(trace_time).div_f64(time_scale),
+				false => Duration::ZERO,
+			};
+
+			for (access, is_warmup) in receiver {
+				if should_stop() {
+					break;
+				}
+
+				let scheduled = match correct_omission {
+					true => {
+						let (start_instant, start_timestamp) = *schedule_start
+							.get_or_insert_with(|| (Instant::now(), access.timestamp));
+
+						start_instant + scale(access.timestamp - start_timestamp)
+					},
+
+					false => {
+						let gap = access.timestamp - prev_timestamp.unwrap_or(access.timestamp);
+						Instant::now() + scale(gap)
+					},
+				};
+
+				let timestamp = access.timestamp;
+				prev_timestamp = Some(timestamp);
+
+				spin_sleep::sleep(scheduled.saturating_duration_since(Instant::now()));
+
+				let event = match is_warmup {
+					true => ClientEvent::Warmup(access),
+					false => ClientEvent::Access(access, correct_omission.then_some(scheduled)),
+				};
+
+				dispatcher.send(event)
+					.expect("Could not send access to client.");
+
+				scheduling_errors.push(scheduled.elapsed().as_micros() as f64);
+
+				let dispatch = (Instant::now(), timestamp);
+				first_dispatch.get_or_insert(dispatch);
+				last_dispatch = Some(dispatch);
+			}
+
+			let (trace_time, wall_time) = match first_dispatch.zip(last_dispatch) {
+				Some(((first_instant, first_timestamp), (last_instant, last_timestamp))) => (
+					Duration::from_millis(last_timestamp - first_timestamp),
+					last_instant.duration_since(first_instant),
+				),
+
+				None => (Duration::ZERO, Duration::ZERO),
+			};
+
+			let stats = ScheduleStats {
+				scheduling_errors,
+				trace_time,
+				wall_time,
+			};
+
+			(dispatcher, stats)
+		});
+
+		Scheduler {
+			sender,
+			handle,
+		}
+	}
+
+	/// Queues an access to be dispatched at its timestamp. Accesses must be
+	/// queued in timestamp order.
+	pub fn schedule(&self, access: Access, is_warmup: bool) -> Result<(), SendError<(Access, bool)>> {
+		self.sender.send((access, is_warmup))
+	}
+
+	/// Waits for every queued access to be dispatched, returning the
+	/// dispatcher and how closely the accesses kept to their schedule.
+	pub fn join(self) -> (Dispatcher, ScheduleStats) {
+		drop(self.sender);
+
+		self.handle
+			.join()
+			.expect("Could not terminate scheduler.")
+	}
+}
@@ -6,12 +6,29 @@
  */
 
 use std::{
-	io,
+	io::{self, BufWriter},
+	collections::HashSet,
+	fs::{self, File, OpenOptions},
 	ops::AddAssign,
 	path::Path,
+	mem,
+	sync::{
+		Arc,
+		atomic::{AtomicBool, AtomicU64, Ordering},
+	},
 	time::{Instant, Duration},
 };
 
+#[cfg(feature = "hdrhistogram")]
+use hdrhistogram::{
+	Histogram,
+	serialization::{
+		V2Serializer,
+		interval_log::{IntervalLogWriterBuilder, Tag},
+	},
+};
+
+use serde::Serialize;
 use statrs::statistics::{Data, OrderStatistics};
 
 use kwik::{
@@ -26,6 +43,7 @@ use kwik::{
 	plot::{
 		Plot,
 		Figure,
+		AxisFormat,
 		line_plot::{LinePlot, Line},
 	},
 	file::{
@@ -35,369 +53,2071 @@ use kwik::{
 	tma::TimeMovingAverage,
 };
 
+use crate::{
+	access::Command,
+	distribution::{LatencyDistribution, LatencyDigest},
+	metrics::LiveMetrics,
+	scheduler::ScheduleStats,
+	raw::RawOutput,
+	timer::{Timer, TimerInstant},
+};
+
 type LatencyData = Data<Vec<f64>>;
 
+const CDF_PLOT_POINTS: usize = 1000;
+
+// the z-score of a two-sided 95% confidence interval
+const CONFIDENCE_Z: f64 = 1.96;
+const MIN_PLOT_WINDOW: Duration = Duration::from_millis(1);
+
+pub const DEFAULT_PERCENTILES: &[f64] = &[
+	50.0,
+	75.0,
+	90.0,
+	95.0,
+	99.0,
+	99.9,
+	99.99,
+	99.999,
+	100.0,
+];
+
 #[derive(Debug, Default, Clone)]
 pub struct Stats {
 	ping_latencies: Vec<(Instant, Duration)>,
 	get_latencies: Vec<(Instant, Duration)>,
 	set_latencies: Vec<(Instant, Duration)>,
+	del_latencies: Vec<(Instant, Duration)>,
+	flush_latencies: Vec<(Instant, Duration)>,
+	queue_delays: Vec<(Instant, Duration)>,
+
+	// write-back flushes and queued events are summarized by digests or
+	// sampled like requests, so they are counted separately
+	flush_digest: Option<LatencyDigest>,
+	queue_digest: Option<LatencyDigest>,
+	flush_count: u64,
+	queue_count: u64,
 
 	get_total_size: u64,
 	set_total_size: u64,
+
+	get_hits: u64,
+	get_misses: u64,
+
+	verified_gets: u64,
+	value_mismatches: u64,
+
+	get_accesses: u64,
+	set_accesses: u64,
+	del_accesses: u64,
+	backfills: u64,
+
+	buffered_sets: u64,
+	flushed_sets: u64,
+
+	// the TTLs of successful SETs in seconds, which are summarized by a
+	// digest or sampled like latencies
+	set_ttls: Vec<f64>,
+	set_ttl_digest: Option<LatencyDigest>,
+	sets_with_ttl: u64,
+	sets_without_ttl: u64,
+
+	ping_errors: u64,
+	get_errors: u64,
+	set_errors: u64,
+	del_errors: u64,
+
+	reconnects: u64,
+
+	refused_errors: u64,
+	disconnected_errors: u64,
+	protocol_errors: u64,
+	server_errors: u64,
+	cache_errors: u64,
+	other_errors: u64,
+
+	// the fraction of requests whose latencies are stored, while the
+	// exact number of requests is always counted
+	sample_rate: Option<f64>,
+
+	// when set, latencies are summarized by streaming digests rather than
+	// being stored, so the latency lists stay empty
+	ping_digest: Option<LatencyDigest>,
+	get_digest: Option<LatencyDigest>,
+	set_digest: Option<LatencyDigest>,
+	del_digest: Option<LatencyDigest>,
+
+	ping_count: u64,
+	get_count: u64,
+	set_count: u64,
+	del_count: u64,
+
+	live_metrics: Option<Arc<LiveMetrics>>,
+
+	// when set, GET latencies are also split by whether the key is
+	// one of the trace's most frequently accessed keys
+	hot_keys: Option<Arc<HashSet<String>>>,
+	hot_get_digest: Option<LatencyDigest>,
+	cold_get_digest: Option<LatencyDigest>,
+
+	stats_memory: Option<Arc<StatsMemory>>,
+
+	raw_output: Option<RawOutput>,
+
+	// latencies are measured with the standard clock if not set
+	timer: Option<Arc<dyn Timer>>,
+
+	// the number of requests of each type slower than the threshold
+	slow_threshold: Option<Duration>,
+	slow_pings: u64,
+	slow_gets: u64,
+	slow_sets: u64,
+	slow_dels: u64,
+}
+
+/// Estimates the memory held by what the benchmark stores per request
+/// across every client: the stored latencies and delays, SET TTLs, the
+/// hashes of verified values, and the hot keys. Only the stored items
+/// themselves are counted, not the spare capacity of their collections or
+/// the overhead of hash tables, so the estimate is a lower bound. The
+/// limit is flagged once the estimate exceeds it.
+#[derive(Debug, Default)]
+pub struct StatsMemory {
+	bytes: AtomicU64,
+
+	max_bytes: u64,
+	is_exceeded: AtomicBool,
+}
+
+/// The cause of a failed request, used to break down errors by kind.
+#[derive(Debug, Copy, Clone)]
+pub enum ErrorKind {
+	Refused,
+	Disconnected,
+	Protocol,
+	Server,
+	Cache,
+	Other,
+}
+
+#[derive(Serialize)]
+struct Summary {
+	schema_version: u32,
+	sample_rate: Option<f64>,
+	slow_threshold_us: Option<u64>,
+
+	ping: Option<OperationSummary>,
+	get: Option<OperationSummary>,
+	set: Option<OperationSummary>,
+	del: Option<OperationSummary>,
+
+	// the GET and SET bytes transferred across the whole run
+	total_bytes: u64,
+	bytes_per_op: f64,
+	bandwidth: Option<f64>,
+}
+
+#[derive(Serialize)]
+struct OperationSummary {
+	count: usize,
+
+	avg: f64,
+	p50: f64,
+	p90: f64,
+	p99: f64,
+	p999: f64,
+	min: f64,
+	max: f64,
+	std_dev: f64,
+	cv: f64,
+	iqr: f64,
+
+	bandwidth: Option<f64>,
+
+	// the fraction of GETs which hit, from 0 to 1
+	hit_ratio: Option<f64>,
+
+	// the number of requests slower than the slow threshold, if there is one
+	slow_count: Option<u64>,
 }
 
 struct PercentileLatency {
-	percentile: usize,
+	percentile: f64,
 
 	ping_latency: Option<f64>,
 	get_latency: Option<f64>,
 	set_latency: Option<f64>,
+	del_latency: Option<f64>,
 }
 
-impl Stats {
-	pub fn store_ping_time(&mut self, instant: Instant) {
-		self.ping_latencies.push((instant, instant.elapsed()));
-	}
+struct HistogramBucket {
+	low: f64,
+	high: f64,
 
-	pub fn store_get_time(&mut self, instant: Instant) {
-		self.get_latencies.push((instant, instant.elapsed()));
-	}
+	ping_count: u64,
+	get_count: u64,
+	set_count: u64,
+	del_count: u64,
+}
 
-	pub fn store_get_size(&mut self, size: u64) {
-		self.get_total_size += size;
-	}
+struct PercentileWindow {
+	second: u64,
 
-	pub fn store_set_time(&mut self, instant: Instant) {
-		self.set_latencies.push((instant, instant.elapsed()));
-	}
+	get_percentiles: Option<(f64, f64)>,
+	set_percentiles: Option<(f64, f64)>,
+}
 
-	pub fn store_set_size(&mut self, size: u64) {
-		self.set_total_size += size;
+struct RunSummary {
+	run_name: String,
+	config: String,
+
+	requests: u64,
+	p50: f64,
+	p99: f64,
+	ops_per_second: f64,
+}
+
+struct Throughput {
+	second: u64,
+
+	ping_ops: u64,
+	get_ops: u64,
+	set_ops: u64,
+	del_ops: u64,
+}
+
+impl Stats {
+	/// The version of the CSV and JSON output formats. This is bumped
+	/// whenever a column or field is added, removed, or changes meaning.
+	/// Version 2 added the slow request counts to the JSON summary and
+	/// renamed its `hit_rate` to `hit_ratio`, a fraction from 0 to 1.
+	pub const OUTPUT_SCHEMA_VERSION: u32 = 2;
+
+	/// Sets the fraction of requests whose latencies are stored, to bound
+	/// the memory used by long benchmarks.
+	pub fn with_sample_rate(mut self, sample_rate: Option<f64>) -> Self {
+		self.sample_rate = sample_rate;
+		self
 	}
 
-	pub fn print_ping_stats(&self) {
-		print_stats("PING", &self.ping_latencies);
+	/// Summarizes latencies with streaming digests rather than storing them,
+	/// so memory use does not grow with the number of requests.
+	pub fn with_streaming_percentiles(mut self, streaming_percentiles: bool) -> Self {
+		if streaming_percentiles {
+			self.ping_digest = Some(LatencyDigest::default());
+			self.get_digest = Some(LatencyDigest::default());
+			self.set_digest = Some(LatencyDigest::default());
+			self.del_digest = Some(LatencyDigest::default());
+			self.set_ttl_digest = Some(LatencyDigest::default());
+			self.flush_digest = Some(LatencyDigest::default());
+			self.queue_digest = Some(LatencyDigest::default());
+		}
+
+		self
 	}
 
-	pub fn print_get_stats(&self) {
-		print_stats("GET", &self.get_latencies);
+	/// Publishes requests, errors, and hits to the supplied live metrics
+	/// as they are stored.
+	pub fn with_live_metrics(mut self, live_metrics: Option<Arc<LiveMetrics>>) -> Self {
+		self.live_metrics = live_metrics;
+		self
+	}
 
-		if self.get_latencies.is_empty() {
-			return;
+	/// Splits GET latencies by whether the key is one of the supplied hot
+	/// keys or not.
+	pub fn with_hot_keys(mut self, hot_keys: Option<Arc<HashSet<String>>>) -> Self {
+		if hot_keys.is_some() {
+			self.hot_get_digest = Some(LatencyDigest::default());
+			self.cold_get_digest = Some(LatencyDigest::default());
 		}
 
-		let avg_size = (self.get_total_size as f64 / self.get_latencies.len() as f64) as u64;
+		self.hot_keys = hot_keys;
+		self
+	}
 
-		println!(
-			"Avg GET size:\t{} ({} B)",
-			fmt::memory(avg_size, Some(2)),
-			fmt::number(avg_size),
-		);
+	/// Counts every stored latency towards the supplied memory estimate.
+	pub fn with_stats_memory(mut self, stats_memory: Option<Arc<StatsMemory>>) -> Self {
+		self.stats_memory = stats_memory;
+		self
+	}
 
-		let total_time = self.get_latencies
-			.iter()
-			.map(|(_, duration)| duration)
-			.sum::<Duration>();
+	/// Streams a record of every request to the supplied raw output as it
+	/// completes.
+	pub fn with_raw_output(mut self, raw_output: Option<RawOutput>) -> Self {
+		self.raw_output = raw_output;
+		self
+	}
 
-		let bandwidth = self.get_total_size as f64 / total_time.as_secs_f64();
+	/// Measures latencies with the supplied timer.
+	pub fn with_timer(mut self, timer: Option<Arc<dyn Timer>>) -> Self {
+		self.timer = timer;
+		self
+	}
 
-		println!(
-			"Bandwidth:\t{}/s ({} B/s)",
-			fmt::memory(bandwidth, Some(2)),
-			fmt::number(bandwidth.round()),
-		);
+	/// Counts the requests of each type whose latency exceeds the threshold.
+	pub fn with_slow_threshold(mut self, slow_threshold: Option<Duration>) -> Self {
+		self.slow_threshold = slow_threshold;
+		self
 	}
 
-	pub fn print_set_stats(&self) {
-		print_stats("SET", &self.set_latencies);
+	/// Returns an empty set of stats which records requests in the same way.
+	pub fn empty_like(&self) -> Self {
+		Stats::default()
+			.with_sample_rate(self.sample_rate)
+			.with_streaming_percentiles(self.ping_digest.is_some())
+			.with_live_metrics(self.live_metrics.clone())
+			.with_hot_keys(self.hot_keys.clone())
+			.with_stats_memory(self.stats_memory.clone())
+			.with_raw_output(self.raw_output.clone())
+			.with_timer(self.timer.clone())
+			.with_slow_threshold(self.slow_threshold)
+	}
 
-		if self.set_latencies.is_empty() {
-			return;
+	pub fn store_ping_time(&mut self, start: TimerInstant) {
+		let instant = start.instant;
+		let latency = self.elapsed(start);
+
+		if self.is_slow(latency) {
+			self.slow_pings += 1;
 		}
 
-		let avg_size = (self.set_total_size as f64 / self.set_latencies.len() as f64) as u64;
+		if let Some(live_metrics) = &self.live_metrics {
+			live_metrics.store_request(latency);
+		}
 
-		println!(
-			"Avg SET size:\t{} ({} B)",
-			fmt::memory(avg_size, Some(2)),
-			fmt::number(avg_size),
-		);
+		if let Some(raw_output) = &self.raw_output {
+			raw_output.store(None, instant, latency, 0, None);
+		}
 
-		let total_time = self.set_latencies
-			.iter()
-			.map(|(_, duration)| duration)
-			.sum::<Duration>();
+		if let Some(digest) = &mut self.ping_digest {
+			digest.push(latency.as_micros() as f64);
+		} else if self.should_sample(self.ping_count) {
+			self.ping_latencies.push((instant, latency));
+			self.track_latency();
+		}
 
-		let bandwidth = self.set_total_size as f64 / total_time.as_secs_f64();
+		self.ping_count += 1;
+	}
 
-		println!(
-			"Bandwidth:\t{}/s ({} B/s)",
-			fmt::memory(bandwidth, Some(2)),
-			fmt::number(bandwidth.round()),
-		);
+	/// Records a completed request to the raw output, if there is one. The
+	/// hit is only recorded for GETs.
+	pub fn store_raw(&self, command: &Command, start: TimerInstant, size: u64, hit: Option<bool>) {
+		if let Some(raw_output) = &self.raw_output {
+			raw_output.store(Some(command), start.instant, self.elapsed(start), size, hit);
+		}
 	}
 
-	pub fn save_latency_percentiles<P>(&self, path: P) -> io::Result<()>
-	where
-		P: AsRef<Path>,
-	{
-		let mut headers: Vec<&str> = vec!["Percentile"];
+	pub fn store_get_time(&mut self, start: TimerInstant, key: &str) {
+		let instant = start.instant;
+		let latency = self.elapsed(start);
 
-		if !self.ping_latencies.is_empty() {
-			headers.push("Ping");
+		if self.is_slow(latency) {
+			self.slow_gets += 1;
 		}
 
-		if !self.get_latencies.is_empty() {
-			headers.push("Get");
+		if let Some(live_metrics) = &self.live_metrics {
+			live_metrics.store_request(latency);
 		}
 
-		if !self.set_latencies.is_empty() {
-			headers.push("Set");
-		}
+		if let Some(hot_keys) = &self.hot_keys {
+			let digest = match hot_keys.contains(key) {
+				true => &mut self.hot_get_digest,
+				false => &mut self.cold_get_digest,
+			};
 
-		let mut writer = CsvWriter::<PercentileLatency>::from_path(path)?
-			.with_headers(&headers)?;
+			if let Some(digest) = digest {
+				digest.push(latency.as_micros() as f64);
+			}
+		}
 
-		let ping_latencies = self.ping_latencies
-			.iter()
-			.map(|(_, duration)| duration.as_micros() as f64)
-			.collect::<Vec<_>>();
+		if let Some(digest) = &mut self.get_digest {
+			digest.push(latency.as_micros() as f64);
+		} else if self.should_sample(self.get_count) {
+			self.get_latencies.push((instant, latency));
+			self.track_latency();
+		}
 
-		let get_latencies = self.get_latencies
-			.iter()
-			.map(|(_, duration)| duration.as_micros() as f64)
-			.collect::<Vec<_>>();
+		self.get_count += 1;
+	}
 
-		let set_latencies = self.set_latencies
-			.iter()
-			.map(|(_, duration)| duration.as_micros() as f64)
-			.collect::<Vec<_>>();
+	pub fn store_get_size(&mut self, size: u64) {
+		self.get_total_size += size;
+	}
 
-		let mut ping_data = Data::new(ping_latencies);
-		let mut get_data = Data::new(get_latencies);
-		let mut set_data = Data::new(set_latencies);
+	pub fn store_get_hit(&mut self) {
+		self.get_hits += 1;
 
-		for percentile in 1..=100 {
-			let ping_latency = if !self.ping_latencies.is_empty() {
-				Some(ping_data.percentile(percentile))
-			} else {
-				None
-			};
+		if let Some(live_metrics) = &self.live_metrics {
+			live_metrics.store_get_hit();
+		}
+	}
 
-			let get_latency = if !self.get_latencies.is_empty() {
-				Some(get_data.percentile(percentile))
-			} else {
-				None
-			};
+	pub fn store_get_miss(&mut self) {
+		self.get_misses += 1;
 
-			let set_latency = if !self.set_latencies.is_empty() {
-				Some(set_data.percentile(percentile))
-			} else {
-				None
-			};
+		if let Some(live_metrics) = &self.live_metrics {
+			live_metrics.store_get_miss();
+		}
+	}
 
-			let percentile_latency = PercentileLatency {
-				percentile,
+	/// Records whether the value of a GET hit matched the value last SET
+	/// to its key.
+	pub fn store_value_check(&mut self, is_match: bool) {
+		self.verified_gets += 1;
 
-				ping_latency,
-				get_latency,
-				set_latency,
-			};
+		if !is_match {
+			self.value_mismatches += 1;
+		}
+	}
 
-			writer.write_row(&percentile_latency)?;
+	/// Records an access received from the trace, whether or not the
+	/// client type sends it to the cache.
+	pub fn store_access(&mut self, command: &Command) {
+		match command {
+			Command::Get => self.get_accesses += 1,
+			Command::Set => self.set_accesses += 1,
+			Command::Del => self.del_accesses += 1,
 		}
+	}
 
-		Ok(())
+	pub fn store_backfill(&mut self) {
+		self.backfills += 1;
 	}
 
-	pub fn save_latency_plot<P>(&self, path: P) -> io::Result<()>
-	where
-		P: AsRef<Path>,
-	{
-		let mut plot = LinePlot::default()
-			.with_title("Paper latency")
-			.with_x_label("Time (s)")
-			.with_y_label("Latency (us)")
-			.with_x_min(0)
-			.with_y_min(0);
+	pub fn store_set_time(&mut self, start: TimerInstant) {
+		let instant = start.instant;
+		let latency = self.elapsed(start);
 
-		let mut ping_line = Line::default().with_label("Ping");
-		let mut get_line = Line::default().with_label("Get");
-		let mut set_line = Line::default().with_label("Set");
+		if self.is_slow(latency) {
+			self.slow_sets += 1;
+		}
 
-		if let Some((initial_instant, final_instant)) = self.get_initial_instant().zip(self.get_final_instant()) {
-			plot.set_x_max(final_instant.duration_since(initial_instant).as_secs_f64());
+		if let Some(live_metrics) = &self.live_metrics {
+			live_metrics.store_request(latency);
+		}
 
-			let mut ping_tma = TimeMovingAverage::default();
-			let mut get_tma = TimeMovingAverage::default();
-			let mut set_tma = TimeMovingAverage::default();
+		if let Some(digest) = &mut self.set_digest {
+			digest.push(latency.as_micros() as f64);
+		} else if self.should_sample(self.set_count) {
+			self.set_latencies.push((instant, latency));
+			self.track_latency();
+		}
 
-			for (instant, duration) in &self.ping_latencies {
-				ping_tma.push(*instant, duration.as_micros());
-			}
+		self.set_count += 1;
+	}
 
-			for (instant, duration) in &self.get_latencies {
-				get_tma.push(*instant, duration.as_micros());
-			}
+	pub fn store_set_size(&mut self, size: u64) {
+		self.set_total_size += size;
+	}
 
-			for (instant, duration) in &self.set_latencies {
-				set_tma.push(*instant, duration.as_micros());
-			}
+	pub fn store_set_ttl(&mut self, ttl: Option<Duration>) {
+		let Some(ttl) = ttl else {
+			self.sets_without_ttl += 1;
+			return;
+		};
 
-			let window = final_instant.duration_since(initial_instant) / 50;
+		if let Some(digest) = &mut self.set_ttl_digest {
+			digest.push(ttl.as_secs_f64());
+		} else if self.should_sample(self.sets_with_ttl) {
+			self.set_ttls.push(ttl.as_secs_f64());
+			self.track_memory(mem::size_of::<f64>());
+		}
 
-			for (instant, value) in ping_tma.window_iter(window) {
-				ping_line.push(
-					instant.duration_since(initial_instant).as_secs_f64(),
-					value,
-				);
-			}
+		self.sets_with_ttl += 1;
+	}
 
-			for (instant, value) in get_tma.window_iter(window) {
-				get_line.push(
-					instant.duration_since(initial_instant).as_secs_f64(),
-					value,
-				);
-			}
+	pub fn store_del_time(&mut self, start: TimerInstant) {
+		let instant = start.instant;
+		let latency = self.elapsed(start);
 
-			for (instant, value) in set_tma.window_iter(window) {
-				set_line.push(
-					instant.duration_since(initial_instant).as_secs_f64(),
-					value,
-				);
-			}
+		if self.is_slow(latency) {
+			self.slow_dels += 1;
 		}
 
-		if !ping_line.is_empty() {
-			plot.line(ping_line);
+		if let Some(live_metrics) = &self.live_metrics {
+			live_metrics.store_request(latency);
 		}
 
-		if !get_line.is_empty() {
-			plot.line(get_line);
+		if let Some(digest) = &mut self.del_digest {
+			digest.push(latency.as_micros() as f64);
+		} else if self.should_sample(self.del_count) {
+			self.del_latencies.push((instant, latency));
+			self.track_latency();
 		}
 
-		if !set_line.is_empty() {
-			plot.line(set_line);
-		}
+		self.del_count += 1;
+	}
 
-		let mut figure = Figure::default();
+	pub fn store_flush_time(&mut self, start: TimerInstant) {
+		let latency = self.elapsed(start);
 
-		figure.add(plot);
-		figure.save(path)
+		if let Some(digest) = &mut self.flush_digest {
+			digest.push(latency.as_micros() as f64);
+		} else if self.should_sample(self.flush_count) {
+			self.flush_latencies.push((start.instant, latency));
+			self.track_latency();
+		}
+
+		self.flush_count += 1;
 	}
 
-	fn get_initial_instant(&self) -> Option<Instant> {
-		let ping_initial_instant = self.ping_latencies.first().map(|(instant, _)| *instant);
-		let get_initial_instant = self.get_latencies.first().map(|(instant, _)| *instant);
-		let set_initial_instant = self.set_latencies.first().map(|(instant, _)| *instant);
+	/// Records the time between an event being enqueued and a client
+	/// starting to handle it.
+	pub fn store_queue_time(&mut self, enqueued: Instant) {
+		let delay = enqueued.elapsed();
 
-		let instants = &[ping_initial_instant, get_initial_instant, set_initial_instant]
-			.iter()
-			.flatten()
-			.copied()
-			.collect::<Vec<_>>();
+		if let Some(digest) = &mut self.queue_digest {
+			digest.push(delay.as_micros() as f64);
+		} else if self.should_sample(self.queue_count) {
+			self.queue_delays.push((enqueued, delay));
+			self.track_latency();
+		}
 
-		math::min(instants).copied()
+		self.queue_count += 1;
 	}
 
-	fn get_final_instant(&self) -> Option<Instant> {
-		let ping_final_instant = self.ping_latencies.last().map(|(instant, _)| *instant);
-		let get_final_instant = self.get_latencies.last().map(|(instant, _)| *instant);
-		let set_final_instant = self.set_latencies.last().map(|(instant, _)| *instant);
-
-		let instants = &[ping_final_instant, get_final_instant, set_final_instant]
-			.iter()
-			.flatten()
-			.copied()
-			.collect::<Vec<_>>();
+	pub fn store_buffered_set(&mut self) {
+		self.buffered_sets += 1;
+	}
 
-		math::max(instants).copied()
+	pub fn store_flushed_sets(&mut self, count: u64) {
+		self.flushed_sets += count;
 	}
-}
 
-impl AddAssign for Stats {
-	fn add_assign(&mut self, rhs: Self) {
-		*self = Stats {
-			ping_latencies: merge_times(&self.ping_latencies, &rhs.ping_latencies),
-			get_latencies: merge_times(&self.get_latencies, &rhs.get_latencies),
-			set_latencies: merge_times(&self.set_latencies, &rhs.set_latencies),
+	pub fn store_ping_error(&mut self) {
+		self.ping_errors += 1;
+	}
 
-			get_total_size: self.get_total_size + rhs.get_total_size,
-			set_total_size: self.set_total_size + rhs.set_total_size,
-		}
+	pub fn store_get_error(&mut self) {
+		self.get_errors += 1;
 	}
-}
 
-fn print_stats(label: &'static str, times: &[(Instant, Duration)]) {
-	let latencies = times
-		.iter()
-		.map(|(_, duration)| duration.as_micros() as f64)
-		.collect::<Vec<_>>();
+	pub fn store_set_error(&mut self) {
+		self.set_errors += 1;
+	}
 
-	let mut data = Data::new(latencies);
+	pub fn store_del_error(&mut self) {
+		self.del_errors += 1;
+	}
 
-	if data.is_empty() {
-		return;
+	pub fn store_reconnect(&mut self) {
+		self.reconnects += 1;
 	}
 
-	println!("\n*** {label} stats ***\n");
+	pub fn store_error_kind(&mut self, kind: ErrorKind) {
+		if let Some(live_metrics) = &self.live_metrics {
+			live_metrics.store_error();
+		}
 
-	print_dist(&mut data);
-	print_simple_stats(label, &data);
-}
+		match kind {
+			ErrorKind::Refused => self.refused_errors += 1,
+			ErrorKind::Disconnected => self.disconnected_errors += 1,
+			ErrorKind::Protocol => self.protocol_errors += 1,
+			ErrorKind::Server => self.server_errors += 1,
+			ErrorKind::Cache => self.cache_errors += 1,
+			ErrorKind::Other => self.other_errors += 1,
+		}
+	}
 
-fn print_dist(data: &mut LatencyData) {
-	let mut table = Table::default();
+	pub fn print_ping_stats(&self, percentiles: &[f64]) {
+		print_stats(
+			"PING",
+			self.get_ping_distribution(),
+			self.ping_errors,
+			self.get_slow_ops(self.slow_pings),
+			percentiles,
+			self.sample_rate,
+		);
 
-	let quantiles: &[f64] = &[
-		0.5,
-		0.75,
-		0.90,
-		0.95,
-		0.99,
-		0.999,
-		0.9999,
-		0.99999,
-		1.0,
-	];
+		if self.ping_count == 0 {
+			return;
+		}
 
-	let mut header = Row::default();
-	let mut row = Row::default();
+		println!("Total pings:\t{}", fmt::number(self.ping_count));
 
-	for quantile in quantiles {
-		let multiplier = match quantile {
-			0.999 => 1000.0,
-			0.9999 => 10000.0,
-			0.99999 => 100000.0,
-			_ => 100.0,
-		};
+		// the wall time is only known when the latencies are stored
+		if self.ping_latencies.is_empty() {
+			return;
+		}
 
-		let label = format!("p{}", (quantile * multiplier).round());
-		let value = format!("{:.0}us", data.quantile(*quantile));
+		let wall_time = get_wall_time(&self.ping_latencies);
+		let rate = self.ping_count as f64 / wall_time.as_secs_f64();
 
-		header = header.push(label, Align::Center, Style::Bold);
-		row = row.push(value, Align::Center, Style::Normal);
+		println!("Duration:\t{:.3}s", wall_time.as_secs_f64());
+		println!("Throughput:\t{} pings/sec", fmt::number(rate.round() as u64));
 	}
 
-	table.set_header(header);
-	table.add_row(row);
+	pub fn print_get_stats(&self, percentiles: &[f64]) {
+		print_stats(
+			"GET",
+			self.get_get_distribution(),
+			self.get_errors,
+			self.get_slow_ops(self.slow_gets),
+			percentiles,
+			self.sample_rate,
+		);
 
-	let mut stdout = io::stdout().lock();
-	table.print(&mut stdout);
+		if self.get_count == 0 {
+			return;
+		}
+
+		println!("Hit rate:\t{:.1}%", self.get_hit_rate() * 100.0);
+
+		if self.verified_gets > 0 {
+			println!(
+				"Mismatches:\t{} of {} verified",
+				fmt::number(self.value_mismatches),
+				fmt::number(self.verified_gets),
+			);
+		}
+
+		let avg_size = (self.get_total_size as f64 / self.get_count as f64) as u64;
+
+		println!(
+			"Avg GET size:\t{} ({} B)",
+			fmt::memory(avg_size, Some(2)),
+			fmt::number(avg_size),
+		);
+
+		let bandwidth = get_bandwidth(self.get_total_size, &self.get_get_distribution(), self.get_count);
+
+		println!(
+			"Bandwidth:\t{}/s ({} B/s)",
+			fmt::memory(bandwidth, Some(2)),
+			fmt::number(bandwidth.round()),
+		);
+	}
+
+	/// Prints the GET latencies of the hot keys and the remaining cold keys.
+	pub fn print_popularity_stats(&self, percentiles: &[f64]) {
+		let Some(hot_keys) = &self.hot_keys else {
+			return;
+		};
+
+		println!("\n*** GET popularity stats (estimated) ***\n");
+		println!("Hot keys:\t{}", fmt::number(hot_keys.len()));
+
+		let buckets = [
+			("Hot GETs", &self.hot_get_digest),
+			("Cold GETs", &self.cold_get_digest),
+		];
+
+		for (label, digest) in buckets {
+			let Some(digest) = digest else {
+				continue;
+			};
+
+			let mut digest = digest.clone();
+			digest.flush();
+
+			let mut data = LatencyDistribution::Streaming(digest);
+
+			if data.is_empty() {
+				continue;
+			}
+
+			println!("\n{label}: {} requests\n", fmt::number(data.len()));
+			print_dist(&mut data, percentiles);
+		}
+	}
+
+	pub fn print_set_stats(&self, percentiles: &[f64]) {
+		print_stats(
+			"SET",
+			self.get_set_distribution(),
+			self.set_errors,
+			self.get_slow_ops(self.slow_sets),
+			percentiles,
+			self.sample_rate,
+		);
+
+		if self.set_count == 0 {
+			return;
+		}
+
+		let avg_size = (self.set_total_size as f64 / self.set_count as f64) as u64;
+
+		println!(
+			"Avg SET size:\t{} ({} B)",
+			fmt::memory(avg_size, Some(2)),
+			fmt::number(avg_size),
+		);
+
+		let bandwidth = get_bandwidth(self.set_total_size, &self.get_set_distribution(), self.set_count);
+
+		println!(
+			"Bandwidth:\t{}/s ({} B/s)",
+			fmt::memory(bandwidth, Some(2)),
+			fmt::number(bandwidth.round()),
+		);
+	}
+
+	pub fn print_ttl_stats(&self) {
+		let total = self.sets_with_ttl + self.sets_without_ttl;
+
+		if total == 0 {
+			return;
+		}
+
+		println!("\n*** TTL stats ***\n");
+
+		println!(
+			"SETs without TTL:\t{:.1}%",
+			self.sets_without_ttl as f64 / total as f64 * 100.0,
+		);
+
+		let mut ttls = match &self.set_ttl_digest {
+			Some(digest) => {
+				let mut digest = digest.clone();
+				digest.flush();
+
+				LatencyDistribution::Streaming(digest)
+			},
+
+			None => LatencyDistribution::Exact(Data::new(self.set_ttls.clone())),
+		};
+
+		if ttls.is_empty() {
+			return;
+		}
+
+		println!("TTL p50:\t\t{:.3}s", ttls.quantile(0.5));
+		println!("TTL p99:\t\t{:.3}s", ttls.quantile(0.99));
+	}
+
+	pub fn print_del_stats(&self, percentiles: &[f64]) {
+		print_stats(
+			"DEL",
+			self.get_del_distribution(),
+			self.del_errors,
+			self.get_slow_ops(self.slow_dels),
+			percentiles,
+			self.sample_rate,
+		);
+	}
+
+	pub fn print_write_back_stats(&self, percentiles: &[f64]) {
+		if self.buffered_sets == 0 {
+			return;
+		}
+
+		let distribution = get_distribution(&self.flush_latencies, &self.flush_digest);
+		print_stats("FLUSH", distribution, 0, None, percentiles, self.sample_rate);
+
+		println!("Buffered SETs:\t{}", fmt::number(self.buffered_sets));
+		println!("Flushed SETs:\t{}", fmt::number(self.flushed_sets));
+	}
+
+	pub fn print_queue_stats(&self, percentiles: &[f64]) {
+		let mut data = get_distribution(&self.queue_delays, &self.queue_digest);
+
+		if data.is_empty() {
+			return;
+		}
+
+		let avg_delay = data.mean();
+
+		println!("\n*** Queueing delay stats ***\n");
+		print_dist(&mut data, percentiles);
+
+		println!("\nAvg delay:\t{}us", avg_delay.round());
+		println!("Max delay:\t{}us", data.max().round());
+	}
+
+	pub fn print_drain_stats(&self, percentiles: &[f64]) {
+		let latencies = [
+			&self.ping_latencies,
+			&self.get_latencies,
+			&self.set_latencies,
+			&self.del_latencies,
+		]
+			.into_iter()
+			.flatten()
+			.map(|(_, duration)| duration.as_micros() as f64)
+			.collect::<Vec<_>>();
+
+		let data = LatencyDistribution::Exact(Data::new(latencies));
+		print_stats("DRAIN", data, 0, None, percentiles, self.sample_rate);
+	}
+
+	/// Prints the total number of operations across the run and the overall
+	/// throughput from the first request to the last.
+	pub fn print_overall_stats(&self) {
+		let Some(duration) = self.get_overall_duration() else {
+			return;
+		};
+
+		let requests = self.get_request_count();
+
+		println!("\n*** Overall stats ***\n");
+
+		println!(
+			"Overall: {} ops in {:.1}s = {} ops/sec",
+			fmt::number(requests),
+			duration,
+			fmt::number((requests as f64 / duration).round() as u64),
+		);
+
+		let total_size = self.get_total_size + self.set_total_size;
+
+		if total_size == 0 {
+			return;
+		}
+
+		let bandwidth = (total_size as f64 / duration).round() as u64;
+		let bytes_per_op = (total_size as f64 / requests as f64).round() as u64;
+
+		println!(
+			"Transferred:\t{} ({} B)",
+			fmt::memory(total_size, Some(2)),
+			fmt::number(total_size),
+		);
+
+		println!(
+			"Bandwidth:\t{}/s ({} B/s)",
+			fmt::memory(bandwidth, Some(2)),
+			fmt::number(bandwidth),
+		);
+
+		println!("Avg bytes/op:\t{} B", fmt::number(bytes_per_op));
+	}
+
+	pub fn print_command_mix(&self) {
+		let total = self.get_accesses + self.set_accesses + self.del_accesses;
+
+		if total == 0 {
+			return;
+		}
+
+		let percent = |count: u64| count as f64 / total as f64 * 100.0;
+
+		let mut mix = format!(
+			"{:.1}% GET / {:.1}% SET",
+			percent(self.get_accesses),
+			percent(self.set_accesses),
+		);
+
+		if self.del_accesses > 0 {
+			mix += &format!(" / {:.1}% DEL", percent(self.del_accesses));
+		}
+
+		println!("\n*** Command mix ***\n");
+		println!("Command mix:\t{mix}");
+
+		if self.backfills > 0 {
+			println!("Backfill SETs:\t{}", fmt::number(self.backfills));
+		}
+	}
+
+	pub fn print_connection_stats(&self) {
+		if self.reconnects == 0 {
+			return;
+		}
+
+		println!("\n*** Connection stats ***\n");
+		println!("Reconnects:\t{}", fmt::number(self.reconnects));
+	}
+
+	pub fn print_error_stats(&self) {
+		let kinds = [
+			("Refused", self.refused_errors),
+			("Disconnected", self.disconnected_errors),
+			("Protocol", self.protocol_errors),
+			("Server", self.server_errors),
+			("Cache", self.cache_errors),
+			("Other", self.other_errors),
+		];
+
+		if kinds.iter().all(|(_, count)| *count == 0) {
+			return;
+		}
+
+		println!("\n*** Error stats ***\n");
+
+		let mut table = Table::default();
+
+		let mut header = Row::default();
+		let mut row = Row::default();
+
+		for (label, count) in kinds {
+			header = header.push(label, Align::Center, Style::Bold);
+			row = row.push(fmt::number(count), Align::Center, Style::Normal);
+		}
+
+		table.set_header(header);
+		table.add_row(row);
+
+		let mut stdout = io::stdout().lock();
+		table.print(&mut stdout);
+	}
+
+	/// Checks each operation against the supplied SLOs, given as pairs of
+	/// a percentile and the latency in microseconds it must not exceed.
+	/// Prints the result of every check and returns true if all passed.
+	pub fn check_slos(&self, slos: &[(f64, f64)]) -> bool {
+		let mut operations = [
+			("PING", self.get_ping_distribution()),
+			("GET", self.get_get_distribution()),
+			("SET", self.get_set_distribution()),
+			("DEL", self.get_del_distribution()),
+		];
+
+		println!("\n*** SLO results ***\n");
+
+		// the worst violation is the one exceeding its limit by the most
+		let mut worst_violation: Option<(f64, String)> = None;
+
+		for (percentile, limit) in slos {
+			for (label, data) in &mut operations {
+				if data.is_empty() {
+					continue;
+				}
+
+				let latency = data.quantile(percentile / 100.0).round();
+				let description = format!("{label} p{percentile}: {latency}us (limit {limit}us)");
+
+				if latency <= *limit {
+					println!("PASS\t{description}");
+					continue;
+				}
+
+				println!("FAIL\t{description}");
+
+				let ratio = latency / limit;
+
+				if worst_violation.as_ref().is_none_or(|(worst_ratio, _)| ratio > *worst_ratio) {
+					worst_violation = Some((ratio, description));
+				}
+			}
+		}
+
+		match worst_violation {
+			Some((_, description)) => {
+				println!("\nSLO: FAIL (worst violation: {description})");
+				false
+			},
+
+			None => {
+				println!("\nSLO: PASS");
+				true
+			},
+		}
+	}
+
+	/// Checks that the measured hit rate is within the tolerance of the
+	/// expected hit rate, both in percent. Prints the result of the check
+	/// and returns true if it passed.
+	pub fn check_hit_rate(&self, expected: f64, tolerance: f64) -> bool {
+		let hit_rate = self.get_hit_rate() * 100.0;
+		let is_pass = (hit_rate - expected).abs() <= tolerance;
+
+		println!("\n*** Hit rate check ***\n");
+		println!(
+			"{}\tHit rate: {hit_rate:.1}% (expected {expected:.1}% ± {tolerance:.1}%)",
+			if is_pass { "PASS" } else { "FAIL" },
+		);
+
+		is_pass
+	}
+
+	/// Prints a summary of every operation in a single table.
+	pub fn print_combined_table(&self) {
+		let operations = [
+			("PING", self.ping_count, self.get_ping_distribution()),
+			("GET", self.get_count, self.get_get_distribution()),
+			("SET", self.set_count, self.get_set_distribution()),
+			("DEL", self.del_count, self.get_del_distribution()),
+		];
+
+		if operations.iter().all(|(_, count, _)| *count == 0) {
+			return;
+		}
+
+		println!("\n*** Combined stats ***\n");
+
+		let mut table = Table::default();
+		let mut header = Row::default();
+
+		for label in ["Op", "Count", "Avg", "p50", "p99", "p999", "Max", "Ops/sec"] {
+			header = header.push(label, Align::Center, Style::Bold);
+		}
+
+		table.set_header(header);
+
+		for (label, count, mut data) in operations {
+			if data.is_empty() {
+				continue;
+			}
+
+			let latency = |latency: f64| format!("{}us", latency.round());
+
+			let row = Row::default()
+				.push(label, Align::Center, Style::Bold)
+				.push(fmt::number(count), Align::Center, Style::Normal)
+				.push(latency(data.mean()), Align::Center, Style::Normal)
+				.push(latency(data.quantile(0.5)), Align::Center, Style::Normal)
+				.push(latency(data.quantile(0.99)), Align::Center, Style::Normal)
+				.push(latency(data.quantile(0.999)), Align::Center, Style::Normal)
+				.push(latency(data.max()), Align::Center, Style::Normal)
+				.push(fmt::number((1_000_000.0 / data.mean()) as u64), Align::Center, Style::Normal);
+
+			table.add_row(row);
+		}
+
+		let mut stdout = io::stdout().lock();
+		table.print(&mut stdout);
+	}
+
+	pub fn print_brief(&self, label: &str, percentiles: &[f64]) {
+		let latencies = [
+			&self.ping_latencies,
+			&self.get_latencies,
+			&self.set_latencies,
+			&self.del_latencies,
+		]
+			.into_iter()
+			.flatten()
+			.map(|(_, duration)| duration.as_micros() as f64)
+			.collect::<Vec<_>>();
+
+		let mut data = LatencyDistribution::Exact(Data::new(latencies));
+
+		if data.is_empty() {
+			return;
+		}
+
+		println!("\n{label}: {} requests\n", fmt::number(data.len()));
+		print_dist(&mut data, percentiles);
+	}
+
+	/// Saves the latency at each of the supplied percentiles to a CSV file,
+	/// or at every whole percentile if none are supplied.
+	pub fn save_latency_percentiles<P>(&self, path: P, percentiles: Option<&[f64]>) -> io::Result<()>
+	where
+		P: AsRef<Path>,
+	{
+		let percentiles = match percentiles {
+			Some(percentiles) => percentiles.to_vec(),
+			None => (1..=100).map(f64::from).collect(),
+		};
+
+		let mut headers: Vec<&str> = vec!["schema_version", "Percentile"];
+
+		if self.ping_count > 0 {
+			headers.push("Ping");
+		}
+
+		if self.get_count > 0 {
+			headers.push("Get");
+		}
+
+		if self.set_count > 0 {
+			headers.push("Set");
+		}
+
+		if self.del_count > 0 {
+			headers.push("Del");
+		}
+
+		let mut writer = CsvWriter::<PercentileLatency>::from_path(path)?
+			.with_headers(&headers)?;
+
+		let mut ping_data = self.get_ping_distribution();
+		let mut get_data = self.get_get_distribution();
+		let mut set_data = self.get_set_distribution();
+		let mut del_data = self.get_del_distribution();
+
+		for percentile in percentiles {
+			let quantile = percentile / 100.0;
+
+			let ping_latency = if self.ping_count > 0 {
+				Some(ping_data.quantile(quantile))
+			} else {
+				None
+			};
+
+			let get_latency = if self.get_count > 0 {
+				Some(get_data.quantile(quantile))
+			} else {
+				None
+			};
+
+			let set_latency = if self.set_count > 0 {
+				Some(set_data.quantile(quantile))
+			} else {
+				None
+			};
+
+			let del_latency = if self.del_count > 0 {
+				Some(del_data.quantile(quantile))
+			} else {
+				None
+			};
+
+			let percentile_latency = PercentileLatency {
+				percentile,
+
+				ping_latency,
+				get_latency,
+				set_latency,
+				del_latency,
+			};
+
+			writer.write_row(&percentile_latency)?;
+		}
+
+		Ok(())
+	}
+
+	/// Appends a row summarizing the run to a CSV file shared across runs,
+	/// writing the header first if the file is new. The throughput is taken
+	/// over the supplied duration of the run.
+	pub fn append_summary_csv<P>(&self, path: P, run_name: &str, config: &str, duration: Duration) -> io::Result<()>
+	where
+		P: AsRef<Path>,
+	{
+		let is_new = fs::metadata(&path)
+			.map(|metadata| metadata.len() == 0)
+			.unwrap_or(true);
+
+		let file = OpenOptions::new()
+			.create(true)
+			.append(true)
+			.open(path)?;
+
+		let mut writer = CsvWriter::<RunSummary>::from_file(file)?;
+
+		if is_new {
+			writer.set_headers(&["schema_version", "run_name", "config", "requests", "p50", "p99", "ops_per_second"])?;
+		}
+
+		let requests = self.get_request_count();
+		let mut data = self.get_combined_distribution();
+
+		let ops_per_second = match duration.is_zero() {
+			true => 0.0,
+			false => requests as f64 / duration.as_secs_f64(),
+		};
+
+		let summary = RunSummary {
+			run_name: run_name.to_owned(),
+			config: config.to_owned(),
+
+			requests,
+			p50: data.quantile(0.5),
+			p99: data.quantile(0.99),
+			ops_per_second,
+		};
+
+		writer.write_row(&summary)?;
+		writer.flush()
+	}
+
+	/// Saves the number of operations completed in each second of the
+	/// benchmark to a CSV file.
+	pub fn save_throughput_csv<P>(&self, path: P) -> io::Result<()>
+	where
+		P: AsRef<Path>,
+	{
+		let mut writer = CsvWriter::<Throughput>::from_path(path)?
+			.with_headers(&["schema_version", "second", "ping_ops", "get_ops", "set_ops", "del_ops"])?;
+
+		let Some((initial_instant, final_instant)) = self.get_initial_instant().zip(self.get_final_instant()) else {
+			return Ok(());
+		};
+
+		let seconds = final_instant.duration_since(initial_instant).as_secs() + 1;
+
+		let ping_ops = get_ops_per_second(&self.ping_latencies, initial_instant, seconds, self.sample_rate);
+		let get_ops = get_ops_per_second(&self.get_latencies, initial_instant, seconds, self.sample_rate);
+		let set_ops = get_ops_per_second(&self.set_latencies, initial_instant, seconds, self.sample_rate);
+		let del_ops = get_ops_per_second(&self.del_latencies, initial_instant, seconds, self.sample_rate);
+
+		for second in 0..seconds {
+			let index = second as usize;
+
+			let throughput = Throughput {
+				second,
+
+				ping_ops: ping_ops[index],
+				get_ops: get_ops[index],
+				set_ops: set_ops[index],
+				del_ops: del_ops[index],
+			};
+
+			writer.write_row(&throughput)?;
+		}
+
+		Ok(())
+	}
+
+	/// Saves the p50 and p99 GET and SET latencies within each second of the
+	/// benchmark to a CSV file. Seconds without requests are left empty.
+	pub fn save_latency_percentile_timeseries_csv<P>(&self, path: P) -> io::Result<()>
+	where
+		P: AsRef<Path>,
+	{
+		let mut writer = CsvWriter::<PercentileWindow>::from_path(path)?
+			.with_headers(&["schema_version", "second", "get_p50", "get_p99", "set_p50", "set_p99"])?;
+
+		let Some((initial_instant, final_instant)) = self.get_initial_instant().zip(self.get_final_instant()) else {
+			return Ok(());
+		};
+
+		let seconds = final_instant.duration_since(initial_instant).as_secs() + 1;
+
+		let get_percentiles = get_percentiles_per_second(&self.get_latencies, initial_instant, seconds);
+		let set_percentiles = get_percentiles_per_second(&self.set_latencies, initial_instant, seconds);
+
+		for second in 0..seconds {
+			let index = second as usize;
+
+			let window = PercentileWindow {
+				second,
+
+				get_percentiles: get_percentiles[index],
+				set_percentiles: set_percentiles[index],
+			};
+
+			writer.write_row(&window)?;
+		}
+
+		Ok(())
+	}
+
+	/// Saves the number of each operation's latencies falling into log-spaced
+	/// buckets to a CSV file. The first bucket covers latencies below 1us and
+	/// every following power of ten is split into `buckets_per_decade` buckets.
+	pub fn save_histogram_csv<P>(&self, path: P, buckets_per_decade: u32) -> io::Result<()>
+	where
+		P: AsRef<Path>,
+	{
+		let mut writer = CsvWriter::<HistogramBucket>::from_path(path)?
+			.with_headers(&["schema_version", "bucket_low", "bucket_high", "ping_count", "get_count", "set_count", "del_count"])?;
+
+		let max_latency = [
+			&self.ping_latencies,
+			&self.get_latencies,
+			&self.set_latencies,
+			&self.del_latencies,
+		]
+			.into_iter()
+			.flatten()
+			.map(|(_, duration)| duration.as_micros() as f64)
+			.fold(0.0, f64::max);
+
+		let mut edges = vec![0.0, 1.0];
+
+		while edges[edges.len() - 1] <= max_latency {
+			let exponent = (edges.len() - 1) as f64 / buckets_per_decade as f64;
+			edges.push(10f64.powf(exponent));
+		}
+
+		let ping_counts = get_bucket_counts(&self.ping_latencies, &edges);
+		let get_counts = get_bucket_counts(&self.get_latencies, &edges);
+		let set_counts = get_bucket_counts(&self.set_latencies, &edges);
+		let del_counts = get_bucket_counts(&self.del_latencies, &edges);
+
+		for index in 0..edges.len() - 1 {
+			let bucket = HistogramBucket {
+				low: edges[index],
+				high: edges[index + 1],
+
+				ping_count: ping_counts[index],
+				get_count: get_counts[index],
+				set_count: set_counts[index],
+				del_count: del_counts[index],
+			};
+
+			writer.write_row(&bucket)?;
+		}
+
+		Ok(())
+	}
+
+	pub fn save_summary_json<P>(&self, path: P) -> io::Result<()>
+	where
+		P: AsRef<Path>,
+	{
+		let mut get = get_operation_summary(self.get_get_distribution(), self.get_count, self.get_slow_ops(self.slow_gets));
+		let mut set = get_operation_summary(self.get_set_distribution(), self.set_count, self.get_slow_ops(self.slow_sets));
+
+		if let Some(summary) = &mut get {
+			summary.bandwidth = Some(get_bandwidth(self.get_total_size, &self.get_get_distribution(), self.get_count));
+			summary.hit_ratio = Some(self.get_hit_rate());
+		}
+
+		if let Some(summary) = &mut set {
+			summary.bandwidth = Some(get_bandwidth(self.set_total_size, &self.get_set_distribution(), self.set_count));
+		}
+
+		let total_bytes = self.get_total_size + self.set_total_size;
+
+		let bytes_per_op = match self.get_request_count() {
+			0 => 0.0,
+			requests => total_bytes as f64 / requests as f64,
+		};
+
+		let summary = Summary {
+			schema_version: Stats::OUTPUT_SCHEMA_VERSION,
+			sample_rate: self.sample_rate,
+			slow_threshold_us: self.slow_threshold.map(|slow_threshold| slow_threshold.as_micros() as u64),
+
+			ping: get_operation_summary(self.get_ping_distribution(), self.ping_count, self.get_slow_ops(self.slow_pings)),
+			get,
+			set,
+			del: get_operation_summary(self.get_del_distribution(), self.del_count, self.get_slow_ops(self.slow_dels)),
+
+			total_bytes,
+			bytes_per_op,
+			bandwidth: self.get_overall_duration().map(|duration| total_bytes as f64 / duration),
+		};
+
+		let writer = BufWriter::new(File::create(path)?);
+
+		serde_json::to_writer_pretty(writer, &summary)
+			.map_err(io::Error::other)
+	}
+
+	/// Saves a histogram of each operation's latencies to an HdrHistogram
+	/// interval log, with one tagged interval per operation.
+	#[cfg(feature = "hdrhistogram")]
+	pub fn save_hdr_histogram<P>(&self, path: P) -> io::Result<()>
+	where
+		P: AsRef<Path>,
+	{
+		let mut writer = BufWriter::new(File::create(path)?);
+		let mut serializer = V2Serializer::new();
+
+		let mut log = IntervalLogWriterBuilder::new()
+			.add_comment(&format!("schema_version: {}", Stats::OUTPUT_SCHEMA_VERSION))
+			.begin_log_with(&mut writer, &mut serializer)?;
+
+		let Some(initial_instant) = self.get_initial_instant() else {
+			return Ok(());
+		};
+
+		let operations = [
+			("ping", &self.ping_latencies),
+			("get", &self.get_latencies),
+			("set", &self.set_latencies),
+			("del", &self.del_latencies),
+		];
+
+		for (tag, times) in operations {
+			let Some(((first_instant, _), (last_instant, _))) = times.first().zip(times.last()) else {
+				continue;
+			};
+
+			let mut histogram = Histogram::<u64>::new(3)
+				.map_err(io::Error::other)?;
+
+			for (_, duration) in times {
+				histogram
+					.record(duration.as_micros() as u64)
+					.map_err(io::Error::other)?;
+			}
+
+			log
+				.write_histogram(
+					&histogram,
+					first_instant.duration_since(initial_instant),
+					last_instant.duration_since(*first_instant),
+					Tag::new(tag),
+				)
+				.map_err(|err| io::Error::other(err.to_string()))?;
+		}
+
+		Ok(())
+	}
+
+	/// Saves a plot of each operation's moving average latency over time,
+	/// with the run split into `resolution` windows.
+	pub fn save_latency_plot<P>(&self, path: P, resolution: u32) -> io::Result<()>
+	where
+		P: AsRef<Path>,
+	{
+		let mut plot = LinePlot::default()
+			.with_title("Paper latency")
+			.with_x_label("Time (s)")
+			.with_y_label("Latency (us)")
+			.with_x_min(0)
+			.with_y_min(0);
+
+		let mut ping_line = Line::default().with_label("Ping");
+		let mut get_line = Line::default().with_label("Get");
+		let mut set_line = Line::default().with_label("Set");
+		let mut del_line = Line::default().with_label("Del");
+
+		if let Some((initial_instant, final_instant)) = self.get_initial_instant().zip(self.get_final_instant()) {
+			plot.set_x_max(final_instant.duration_since(initial_instant).as_secs_f64());
+
+			let mut ping_tma = TimeMovingAverage::default();
+			let mut get_tma = TimeMovingAverage::default();
+			let mut set_tma = TimeMovingAverage::default();
+			let mut del_tma = TimeMovingAverage::default();
+
+			for (instant, duration) in &self.ping_latencies {
+				ping_tma.push(*instant, duration.as_micros());
+			}
+
+			for (instant, duration) in &self.get_latencies {
+				get_tma.push(*instant, duration.as_micros());
+			}
+
+			for (instant, duration) in &self.set_latencies {
+				set_tma.push(*instant, duration.as_micros());
+			}
+
+			for (instant, duration) in &self.del_latencies {
+				del_tma.push(*instant, duration.as_micros());
+			}
+
+			// a zero-length window would never advance, so very short runs
+			// fall back to the minimum window and produce fewer points
+			let window = (final_instant.duration_since(initial_instant) / resolution.max(1))
+				.max(MIN_PLOT_WINDOW);
+
+			for (instant, value) in ping_tma.window_iter(window) {
+				ping_line.push(
+					instant.duration_since(initial_instant).as_secs_f64(),
+					value,
+				);
+			}
+
+			for (instant, value) in get_tma.window_iter(window) {
+				get_line.push(
+					instant.duration_since(initial_instant).as_secs_f64(),
+					value,
+				);
+			}
+
+			for (instant, value) in set_tma.window_iter(window) {
+				set_line.push(
+					instant.duration_since(initial_instant).as_secs_f64(),
+					value,
+				);
+			}
+
+			for (instant, value) in del_tma.window_iter(window) {
+				del_line.push(
+					instant.duration_since(initial_instant).as_secs_f64(),
+					value,
+				);
+			}
+		}
+
+		if !ping_line.is_empty() {
+			plot.line(ping_line);
+		}
+
+		if !get_line.is_empty() {
+			plot.line(get_line);
+		}
+
+		if !set_line.is_empty() {
+			plot.line(set_line);
+		}
+
+		if !del_line.is_empty() {
+			plot.line(del_line);
+		}
+
+		let mut figure = Figure::default();
+
+		figure.add(plot);
+		figure.save(path)
+	}
+
+	/// Saves a plot of each operation's latency against its percentile. On
+	/// a log scale, the x-axis is `1 / (1 - p)` so that each additional nine
+	/// of the tail gets equal space.
+	pub fn save_percentile_plot<P>(&self, path: P, log_scale: bool) -> io::Result<()>
+	where
+		P: AsRef<Path>,
+	{
+		let mut plot = LinePlot::default()
+			.with_title("Paper latency percentiles")
+			.with_y_label("Latency (us)")
+			.with_y_min(0);
+
+		let quantiles = match log_scale {
+			true => {
+				plot.set_x_label("1 / (1 - percentile)");
+				plot.set_x_format(AxisFormat::Log(10.0));
+
+				(0..=500)
+					.map(|index| 1.0 - 1.0 / 10f64.powf(index as f64 / 100.0))
+					.collect::<Vec<_>>()
+			},
+
+			false => {
+				plot.set_x_label("Percentile");
+				plot.set_x_min(0);
+				plot.set_x_max(100);
+
+				(0..=1000)
+					.map(|index| index as f64 / 1000.0)
+					.collect::<Vec<_>>()
+			},
+		};
+
+		let operations = [
+			("Ping", self.get_ping_distribution()),
+			("Get", self.get_get_distribution()),
+			("Set", self.get_set_distribution()),
+			("Del", self.get_del_distribution()),
+		];
+
+		for (label, mut data) in operations {
+			if data.is_empty() {
+				continue;
+			}
+
+			let mut line = Line::default().with_label(label);
+
+			for quantile in &quantiles {
+				let x = match log_scale {
+					true => 1.0 / (1.0 - quantile),
+					false => quantile * 100.0,
+				};
+
+				line.push(x, data.quantile(*quantile));
+			}
+
+			plot.line(line);
+		}
+
+		let mut figure = Figure::default();
+
+		figure.add(plot);
+		figure.save(path)
+	}
+
+	/// Saves a plot of the cumulative fraction of each operation's requests
+	/// which completed within a given latency.
+	pub fn save_cdf_plot<P>(&self, path: P) -> io::Result<()>
+	where
+		P: AsRef<Path>,
+	{
+		let mut plot = LinePlot::default()
+			.with_title("Paper latency CDF")
+			.with_x_label("Latency (us)")
+			.with_y_label("Cumulative fraction")
+			.with_x_min(0)
+			.with_y_min(0)
+			.with_y_max(1);
+
+		let operations = [
+			("Ping", &self.ping_latencies),
+			("Get", &self.get_latencies),
+			("Set", &self.set_latencies),
+			("Del", &self.del_latencies),
+		];
+
+		for (label, times) in operations {
+			if times.is_empty() {
+				continue;
+			}
+
+			let mut latencies = times
+				.iter()
+				.map(|(_, duration)| duration.as_micros() as f64)
+				.collect::<Vec<_>>();
+
+			latencies.sort_unstable_by(f64::total_cmp);
+
+			let mut line = Line::default().with_label(label);
+
+			// only a bounded number of points are plotted so that large
+			// benchmarks still produce a reasonably sized figure
+			let step = (latencies.len() / CDF_PLOT_POINTS).max(1);
+
+			for index in (0..latencies.len()).step_by(step).chain([latencies.len() - 1]) {
+				line.push(
+					latencies[index],
+					(index + 1) as f64 / latencies.len() as f64,
+				);
+			}
+
+			plot.line(line);
+		}
+
+		let mut figure = Figure::default();
+
+		figure.add(plot);
+		figure.save(path)
+	}
+
+	fn track_latency(&self) {
+		self.track_memory(mem::size_of::<(Instant, Duration)>());
+	}
+
+	fn track_memory(&self, bytes: usize) {
+		if let Some(stats_memory) = &self.stats_memory {
+			stats_memory.store_bytes(bytes as u64);
+		}
+	}
+
+	fn get_ping_distribution(&self) -> LatencyDistribution {
+		get_distribution(&self.ping_latencies, &self.ping_digest)
+	}
+
+	fn get_get_distribution(&self) -> LatencyDistribution {
+		get_distribution(&self.get_latencies, &self.get_digest)
+	}
+
+	fn get_set_distribution(&self) -> LatencyDistribution {
+		get_distribution(&self.set_latencies, &self.set_digest)
+	}
+
+	fn get_del_distribution(&self) -> LatencyDistribution {
+		get_distribution(&self.del_latencies, &self.del_digest)
+	}
+
+	/// Returns the distribution of every operation's latencies together.
+	fn get_combined_distribution(&self) -> LatencyDistribution {
+		let digest = [&self.ping_digest, &self.get_digest, &self.set_digest, &self.del_digest]
+			.into_iter()
+			.cloned()
+			.fold(None, merge_digests);
+
+		if let Some(mut digest) = digest {
+			digest.flush();
+			return LatencyDistribution::Streaming(digest);
+		}
+
+		let latencies = [
+			&self.ping_latencies,
+			&self.get_latencies,
+			&self.set_latencies,
+			&self.del_latencies,
+		]
+			.into_iter()
+			.flatten()
+			.map(|(_, duration)| duration.as_micros() as f64)
+			.collect::<Vec<_>>();
+
+		LatencyDistribution::Exact(Data::new(latencies))
+	}
+
+	fn elapsed(&self, start: TimerInstant) -> Duration {
+		match &self.timer {
+			Some(timer) => timer.elapsed(start),
+			None => start.instant.elapsed(),
+		}
+	}
+
+	fn is_slow(&self, latency: Duration) -> bool {
+		self.slow_threshold.is_some_and(|slow_threshold| latency > slow_threshold)
+	}
+
+	/// Returns the slow threshold along with the number of requests slower
+	/// than it, if there is a threshold.
+	fn get_slow_ops(&self, slow_ops: u64) -> Option<(Duration, u64)> {
+		self.slow_threshold.map(|slow_threshold| (slow_threshold, slow_ops))
+	}
+
+	fn get_request_count(&self) -> u64 {
+		self.ping_count + self.get_count + self.set_count + self.del_count
+	}
+
+	/// Returns the seconds between the first and last stored requests, if
+	/// there are stored requests spanning any time.
+	fn get_overall_duration(&self) -> Option<f64> {
+		let (initial_instant, final_instant) = self.get_initial_instant().zip(self.get_final_instant())?;
+		let duration = final_instant.duration_since(initial_instant).as_secs_f64();
+
+		(duration > 0.0).then_some(duration)
+	}
+
+	/// Returns true if the latency of the request with the supplied index
+	/// should be stored. Requests are sampled at evenly spaced intervals.
+	fn should_sample(&self, index: u64) -> bool {
+		let Some(sample_rate) = self.sample_rate else {
+			return true;
+		};
+
+		((index + 1) as f64 * sample_rate).ceil() > (index as f64 * sample_rate).ceil()
+	}
+
+	fn get_hit_rate(&self) -> f64 {
+		self.get_hits as f64 / (self.get_hits + self.get_misses).max(1) as f64
+	}
+
+	fn get_initial_instant(&self) -> Option<Instant> {
+		let ping_initial_instant = self.ping_latencies.first().map(|(instant, _)| *instant);
+		let get_initial_instant = self.get_latencies.first().map(|(instant, _)| *instant);
+		let set_initial_instant = self.set_latencies.first().map(|(instant, _)| *instant);
+		let del_initial_instant = self.del_latencies.first().map(|(instant, _)| *instant);
+
+		let instants = &[ping_initial_instant, get_initial_instant, set_initial_instant, del_initial_instant]
+			.iter()
+			.flatten()
+			.copied()
+			.collect::<Vec<_>>();
+
+		math::min(instants).copied()
+	}
+
+	fn get_final_instant(&self) -> Option<Instant> {
+		let ping_final_instant = self.ping_latencies.last().map(|(instant, _)| *instant);
+		let get_final_instant = self.get_latencies.last().map(|(instant, _)| *instant);
+		let set_final_instant = self.set_latencies.last().map(|(instant, _)| *instant);
+		let del_final_instant = self.del_latencies.last().map(|(instant, _)| *instant);
+
+		let instants = &[ping_final_instant, get_final_instant, set_final_instant, del_final_instant]
+			.iter()
+			.flatten()
+			.copied()
+			.collect::<Vec<_>>();
+
+		math::max(instants).copied()
+	}
+}
+
+impl AddAssign for Stats {
+	fn add_assign(&mut self, rhs: Self) {
+		*self = Stats {
+			ping_latencies: merge_times(&self.ping_latencies, &rhs.ping_latencies),
+			get_latencies: merge_times(&self.get_latencies, &rhs.get_latencies),
+			set_latencies: merge_times(&self.set_latencies, &rhs.set_latencies),
+			del_latencies: merge_times(&self.del_latencies, &rhs.del_latencies),
+			flush_latencies: merge_times(&self.flush_latencies, &rhs.flush_latencies),
+			queue_delays: merge_times(&self.queue_delays, &rhs.queue_delays),
+
+			flush_digest: merge_digests(self.flush_digest.take(), rhs.flush_digest),
+			queue_digest: merge_digests(self.queue_digest.take(), rhs.queue_digest),
+			flush_count: self.flush_count + rhs.flush_count,
+			queue_count: self.queue_count + rhs.queue_count,
+
+			get_total_size: self.get_total_size + rhs.get_total_size,
+			set_total_size: self.set_total_size + rhs.set_total_size,
+
+			get_hits: self.get_hits + rhs.get_hits,
+			get_misses: self.get_misses + rhs.get_misses,
+
+			verified_gets: self.verified_gets + rhs.verified_gets,
+			value_mismatches: self.value_mismatches + rhs.value_mismatches,
+
+			get_accesses: self.get_accesses + rhs.get_accesses,
+			set_accesses: self.set_accesses + rhs.set_accesses,
+			del_accesses: self.del_accesses + rhs.del_accesses,
+			backfills: self.backfills + rhs.backfills,
+
+			buffered_sets: self.buffered_sets + rhs.buffered_sets,
+			flushed_sets: self.flushed_sets + rhs.flushed_sets,
+
+			set_ttls: [self.set_ttls.as_slice(), rhs.set_ttls.as_slice()].concat(),
+			set_ttl_digest: merge_digests(self.set_ttl_digest.take(), rhs.set_ttl_digest),
+			sets_with_ttl: self.sets_with_ttl + rhs.sets_with_ttl,
+			sets_without_ttl: self.sets_without_ttl + rhs.sets_without_ttl,
+
+			ping_errors: self.ping_errors + rhs.ping_errors,
+			get_errors: self.get_errors + rhs.get_errors,
+			set_errors: self.set_errors + rhs.set_errors,
+			del_errors: self.del_errors + rhs.del_errors,
+
+			reconnects: self.reconnects + rhs.reconnects,
+
+			refused_errors: self.refused_errors + rhs.refused_errors,
+			disconnected_errors: self.disconnected_errors + rhs.disconnected_errors,
+			protocol_errors: self.protocol_errors + rhs.protocol_errors,
+			server_errors: self.server_errors + rhs.server_errors,
+			cache_errors: self.cache_errors + rhs.cache_errors,
+			other_errors: self.other_errors + rhs.other_errors,
+
+			sample_rate: self.sample_rate.or(rhs.sample_rate),
+
+			ping_digest: merge_digests(self.ping_digest.take(), rhs.ping_digest),
+			get_digest: merge_digests(self.get_digest.take(), rhs.get_digest),
+			set_digest: merge_digests(self.set_digest.take(), rhs.set_digest),
+			del_digest: merge_digests(self.del_digest.take(), rhs.del_digest),
+
+			ping_count: self.ping_count + rhs.ping_count,
+			get_count: self.get_count + rhs.get_count,
+			set_count: self.set_count + rhs.set_count,
+			del_count: self.del_count + rhs.del_count,
+
+			live_metrics: self.live_metrics.take().or(rhs.live_metrics),
+
+			hot_keys: self.hot_keys.take().or(rhs.hot_keys),
+			hot_get_digest: merge_digests(self.hot_get_digest.take(), rhs.hot_get_digest),
+			cold_get_digest: merge_digests(self.cold_get_digest.take(), rhs.cold_get_digest),
+
+			stats_memory: self.stats_memory.take().or(rhs.stats_memory),
+
+			// merged stats do not keep the raw writer open
+			raw_output: None,
+
+			timer: self.timer.take().or(rhs.timer),
+
+			slow_threshold: self.slow_threshold.or(rhs.slow_threshold),
+			slow_pings: self.slow_pings + rhs.slow_pings,
+			slow_gets: self.slow_gets + rhs.slow_gets,
+			slow_sets: self.slow_sets + rhs.slow_sets,
+			slow_dels: self.slow_dels + rhs.slow_dels,
+		}
+	}
+}
+
+impl StatsMemory {
+	pub fn new(max_bytes: u64) -> Self {
+		StatsMemory {
+			max_bytes,
+			..StatsMemory::default()
+		}
+	}
+
+	/// Returns the estimated number of bytes held by the stored stats.
+	pub fn bytes(&self) -> u64 {
+		self.bytes.load(Ordering::Relaxed)
+	}
+
+	/// Returns true once the stored stats have exceeded the limit.
+	pub fn is_exceeded(&self) -> bool {
+		self.is_exceeded.load(Ordering::Relaxed)
+	}
+
+	/// Counts a key stored in a map or set, along with the size of the
+	/// value stored with it.
+	pub fn store_key(&self, key: &str, value_size: usize) {
+		self.store_bytes(get_key_size(key, value_size));
+	}
+
+	/// Stops counting a key which has been removed from its map or set.
+	pub fn release_key(&self, key: &str, value_size: usize) {
+		self.bytes.fetch_sub(get_key_size(key, value_size), Ordering::Relaxed);
+	}
+
+	fn store_bytes(&self, bytes: u64) {
+		let bytes = self.bytes.fetch_add(bytes, Ordering::Relaxed) + bytes;
+
+		if bytes > self.max_bytes {
+			self.is_exceeded.store(true, Ordering::Relaxed);
+		}
+	}
+}
+
+/// Prints the minimum, average, and maximum time taken by the clients to
+/// connect and authenticate.
+pub fn print_setup_stats(connect_latencies: &[Duration], auth_latencies: &[Duration]) {
+	print_setup_latency("Connect", connect_latencies);
+	print_setup_latency("Auth", auth_latencies);
 }
 
-fn print_simple_stats(label: &'static str, data: &LatencyData) {
-	let total_time = data
+/// Prints the number of clients which were running on average over the
+/// benchmark, weighting each client by how long it stayed alive.
+pub fn print_concurrency_stats(lifetimes: &[(Instant, Instant)], clients: u32) {
+	let first_start = lifetimes.iter().map(|(start, _)| start).min();
+	let last_end = lifetimes.iter().map(|(_, end)| end).max();
+
+	let Some((first_start, last_end)) = first_start.zip(last_end) else {
+		return;
+	};
+
+	let wall_time = last_end.duration_since(*first_start).as_secs_f64();
+
+	if wall_time == 0.0 {
+		return;
+	}
+
+	let active_time = lifetimes
 		.iter()
+		.map(|(start, end)| end.duration_since(*start).as_secs_f64())
 		.sum::<f64>();
 
+	println!("\n*** Concurrency stats ***\n");
+	println!("Effective clients:\t{:.1} of {clients}", active_time / wall_time);
+}
+
+/// Prints how long the producer was blocked sending events to clients
+/// which had fallen behind.
+pub fn print_stall_stats(sends: u64, stalled_sends: u64, stall_time: Duration) {
+	if sends == 0 {
+		return;
+	}
+
+	let avg_stall = match stalled_sends {
+		0 => Duration::ZERO,
+		stalled_sends => stall_time.div_f64(stalled_sends as f64),
+	};
+
+	println!("\n*** Producer stats ***\n");
+	println!(
+		"Stalled sends:\t{} of {} ({:.1}%)",
+		fmt::number(stalled_sends),
+		fmt::number(sends),
+		stalled_sends as f64 / sends as f64 * 100.0,
+	);
+	println!("Total stall:\t{:.3}s", stall_time.as_secs_f64());
+	println!("Avg stall:\t{}us", avg_stall.as_micros());
+}
+
+/// Prints the distribution of gaps between consecutive accesses of a trace
+/// replayed at its native time, which shows how bursty the trace is.
+pub fn print_inter_arrival_stats(mut inter_arrival_times: LatencyDigest) {
+	inter_arrival_times.flush();
+
+	let mut data = LatencyDistribution::Streaming(inter_arrival_times);
+
+	if data.is_empty() {
+		return;
+	}
+
+	println!("\n*** Inter-arrival stats ***\n");
+	println!("Avg gap:\t{:.3}ms", data.mean());
+	println!("p50 gap:\t{}ms", data.quantile(0.5).round());
+	println!("p99 gap:\t{}ms", data.quantile(0.99).round());
+	println!("Max gap:\t{}ms", data.max().round());
+}
+
+/// Prints how many accesses were tolerated despite going back in time, and
+/// by how much.
+pub fn print_skew_stats(mut skews: LatencyDigest) {
+	skews.flush();
+
+	let data = LatencyDistribution::Streaming(skews);
+
+	if data.is_empty() {
+		return;
+	}
+
+	println!("\n*** Clock skew stats ***\n");
+	println!("Skewed accesses:\t{}", data.len());
+	println!("Avg skew:\t{:.3}ms", data.mean());
+	println!("Max skew:\t{}ms", data.max().round());
+}
+
+/// Prints how late accesses were dispatched relative to their schedule and
+/// how fast the trace was replayed compared to the requested time scale.
+pub fn print_scheduling_stats(schedule_stats: ScheduleStats, time_scale: f64) {
+	let mut scheduling_errors = schedule_stats.scheduling_errors;
+	scheduling_errors.flush();
+
+	let mut data = LatencyDistribution::Streaming(scheduling_errors);
+
+	if data.is_empty() {
+		return;
+	}
+
+	println!("\n*** Scheduling stats ***\n");
+	println!("Avg error:\t{:.3}us", data.mean());
+	println!("p50 error:\t{}us", data.quantile(0.5).round());
+	println!("p99 error:\t{}us", data.quantile(0.99).round());
+	println!("Max error:\t{}us", data.max().round());
+
+	match time_scale > 0.0 {
+		true => println!("Requested speed:\t{time_scale:.2}x"),
+		false => println!("Requested speed:\tmax"),
+	}
+
+	if !schedule_stats.wall_time.is_zero() {
+		let achieved_speed = schedule_stats.trace_time.as_secs_f64() / schedule_stats.wall_time.as_secs_f64();
+		println!("Achieved speed:\t{achieved_speed:.2}x");
+	}
+}
+
+fn print_setup_latency(label: &'static str, latencies: &[Duration]) {
+	let Some(min) = latencies.iter().min() else {
+		return;
+	};
+
+	let max = latencies.iter().max().unwrap_or(min);
+	let avg = latencies.iter().sum::<Duration>() / latencies.len() as u32;
+
+	println!(
+		"{label} latency: min {}us / avg {}us / max {}us",
+		min.as_micros(),
+		avg.as_micros(),
+		max.as_micros(),
+	);
+}
+
+fn print_stats(
+	label: &'static str,
+	mut data: LatencyDistribution,
+	errors: u64,
+	slow_ops: Option<(Duration, u64)>,
+	percentiles: &[f64],
+	sample_rate: Option<f64>,
+) {
+	if data.is_empty() && errors == 0 {
+		return;
+	}
+
+	match (&data, sample_rate) {
+		(LatencyDistribution::Streaming(_), _) => println!("\n*** {label} stats (estimated) ***\n"),
+		(_, Some(sample_rate)) => println!("\n*** {label} stats (sampled at {}%) ***\n", sample_rate * 100.0),
+		_ => println!("\n*** {label} stats ***\n"),
+	}
+
+	if !data.is_empty() {
+		print_dist(&mut data, percentiles);
+		print_simple_stats(label, &mut data);
+	}
+
+	println!("Errors:\t\t{}", fmt::number(errors));
+
+	if let Some((slow_threshold, slow_ops)) = slow_ops {
+		println!(
+			"Slow {label}s (>{}us):\t{}",
+			slow_threshold.as_micros(),
+			fmt::number(slow_ops),
+		);
+	}
+}
+
+fn print_dist(data: &mut LatencyDistribution, percentiles: &[f64]) {
+	let mut table = Table::default();
+
+	let mut header = Row::default();
+	let mut row = Row::default();
+
+	for percentile in percentiles {
+		// scale the percentile so the label has no decimal point,
+		// e.g., 99.9 becomes p999
+		let fraction_digits = percentile
+			.to_string()
+			.split_once('.')
+			.map_or(0, |(_, fraction)| fraction.len());
+
+		let multiplier = 10f64.powi(fraction_digits as i32);
+
+		let label = format!("p{}", (percentile * multiplier).round());
+		let value = format!("{:.0}us", data.quantile(percentile / 100.0));
+
+		header = header.push(label, Align::Center, Style::Bold);
+		row = row.push(value, Align::Center, Style::Normal);
+	}
+
+	table.set_header(header);
+	table.add_row(row);
+
+	let mut stdout = io::stdout().lock();
+	table.print(&mut stdout);
+}
+
+fn print_simple_stats(label: &'static str, data: &mut LatencyDistribution) {
 	println!(
-		"\nAvg latency:\t{}us",
-		(total_time / data.len() as f64).round(),
+		"\nAvg latency:\t{}us ± {:.1}us (95% CI)",
+		data.mean().round(),
+		get_confidence_interval(data),
 	);
+	println!("Min latency:\t{}us", data.min().round());
+	println!("Max latency:\t{}us", data.max().round());
+	println!("Std dev:\t{}us", data.std_dev().round());
+	println!("CV:\t\t{:.3}", get_coefficient_of_variation(data));
+	println!("IQR:\t\t{}us", get_interquartile_range(data).round());
 
-	let rate = data.len() as f64 / (total_time / 1_000_000.0);
+	let p50 = data.quantile(0.5);
+
+	if p50 > 0.0 {
+		println!("Tail amplification (p99/p50):\t{:.1}x", data.quantile(0.99) / p50);
+		println!("Tail amplification (p999/p50):\t{:.1}x", data.quantile(0.999) / p50);
+	}
+
+	let rate = 1_000_000.0 / data.mean();
 
 	println!(
 		"{label}s/sec:\t{}",
@@ -405,6 +2125,181 @@ fn print_simple_stats(label: &'static str, data: &LatencyData) {
 	);
 }
 
+fn get_latency_data(times: &[(Instant, Duration)]) -> LatencyData {
+	let latencies = times
+		.iter()
+		.map(|(_, duration)| duration.as_micros() as f64)
+		.collect::<Vec<_>>();
+
+	Data::new(latencies)
+}
+
+/// Returns the standard deviation relative to the mean, or zero if the
+/// mean is zero.
+fn get_coefficient_of_variation(data: &LatencyDistribution) -> f64 {
+	match data.mean() {
+		mean if mean > 0.0 => data.std_dev() / mean,
+		_ => 0.0,
+	}
+}
+
+/// Returns the half-width of the 95% confidence interval on the mean, from
+/// its standard error.
+fn get_confidence_interval(data: &LatencyDistribution) -> f64 {
+	match data.len() {
+		0 => 0.0,
+		len => CONFIDENCE_Z * data.std_dev() / (len as f64).sqrt(),
+	}
+}
+
+fn get_interquartile_range(data: &mut LatencyDistribution) -> f64 {
+	data.quantile(0.75) - data.quantile(0.25)
+}
+
+/// Returns the bytes held by a key stored in a map or set, along with its
+/// value.
+fn get_key_size(key: &str, value_size: usize) -> u64 {
+	(mem::size_of::<String>() + key.len() + value_size) as u64
+}
+
+fn get_distribution(times: &[(Instant, Duration)], digest: &Option<LatencyDigest>) -> LatencyDistribution {
+	match digest {
+		Some(digest) => {
+			let mut digest = digest.clone();
+			digest.flush();
+
+			LatencyDistribution::Streaming(digest)
+		},
+
+		None => LatencyDistribution::Exact(get_latency_data(times)),
+	}
+}
+
+fn merge_digests(digest_a: Option<LatencyDigest>, digest_b: Option<LatencyDigest>) -> Option<LatencyDigest> {
+	match (digest_a, digest_b) {
+		(Some(digest_a), Some(digest_b)) => Some(digest_a.merge(digest_b)),
+		(digest_a, digest_b) => digest_a.or(digest_b),
+	}
+}
+
+/// Returns the time from the start of the first request to the end of the
+/// last request.
+fn get_wall_time(times: &[(Instant, Duration)]) -> Duration {
+	let Some(((first_instant, _), (last_instant, last_duration))) = times.first().zip(times.last()) else {
+		return Duration::ZERO;
+	};
+
+	(*last_instant + *last_duration).duration_since(*first_instant)
+}
+
+fn get_bandwidth(total_size: u64, data: &LatencyDistribution, count: u64) -> f64 {
+	// the average latency of the stored or sampled requests is scaled up
+	// to estimate the time taken by all requests
+	let total_time = data.mean() / 1_000_000.0 * count as f64;
+
+	total_size as f64 / total_time
+}
+
+fn get_operation_summary(
+	mut data: LatencyDistribution,
+	count: u64,
+	slow_ops: Option<(Duration, u64)>,
+) -> Option<OperationSummary> {
+	if data.is_empty() {
+		return None;
+	}
+
+	let summary = OperationSummary {
+		count: count as usize,
+
+		avg: data.mean(),
+		p50: data.quantile(0.5),
+		p90: data.quantile(0.9),
+		p99: data.quantile(0.99),
+		p999: data.quantile(0.999),
+		min: data.min(),
+		max: data.max(),
+		std_dev: data.std_dev(),
+		cv: get_coefficient_of_variation(&data),
+		iqr: get_interquartile_range(&mut data),
+
+		bandwidth: None,
+		hit_ratio: None,
+
+		slow_count: slow_ops.map(|(_, slow_ops)| slow_ops),
+	};
+
+	Some(summary)
+}
+
+fn get_bucket_counts(times: &[(Instant, Duration)], edges: &[f64]) -> Vec<u64> {
+	let mut counts = vec![0; edges.len() - 1];
+
+	for (_, duration) in times {
+		let latency = duration.as_micros() as f64;
+
+		// the index of the first edge above the latency is one past
+		// the index of the latency's bucket
+		let index = edges
+			.partition_point(|edge| *edge <= latency)
+			.clamp(1, edges.len() - 1);
+
+		counts[index - 1] += 1;
+	}
+
+	counts
+}
+
+/// Returns the p50 and p99 latencies of the requests started within each
+/// second, or `None` for seconds without requests.
+fn get_percentiles_per_second(
+	times: &[(Instant, Duration)],
+	initial_instant: Instant,
+	seconds: u64,
+) -> Vec<Option<(f64, f64)>> {
+	let mut windows = vec![Vec::<f64>::new(); seconds as usize];
+
+	for (instant, duration) in times {
+		let second = instant.duration_since(initial_instant).as_secs() as usize;
+		windows[second].push(duration.as_micros() as f64);
+	}
+
+	windows
+		.into_iter()
+		.map(|latencies| {
+			if latencies.is_empty() {
+				return None;
+			}
+
+			let mut data = Data::new(latencies);
+			Some((data.quantile(0.5), data.quantile(0.99)))
+		})
+		.collect()
+}
+
+fn get_ops_per_second(
+	times: &[(Instant, Duration)],
+	initial_instant: Instant,
+	seconds: u64,
+	sample_rate: Option<f64>,
+) -> Vec<u64> {
+	let mut ops = vec![0; seconds as usize];
+
+	for (instant, _) in times {
+		let second = instant.duration_since(initial_instant).as_secs() as usize;
+		ops[second] += 1;
+	}
+
+	// sampled counts are scaled up to estimate the total number of requests
+	if let Some(sample_rate) = sample_rate {
+		for count in &mut ops {
+			*count = (*count as f64 / sample_rate).round() as u64;
+		}
+	}
+
+	ops
+}
+
 fn merge_times(times_a: &[(Instant, Duration)], times_b: &[(Instant, Duration)]) -> Vec<(Instant, Duration)> {
 	let mut times = Vec::<(Instant, Duration)>::new();
 
@@ -418,6 +2313,7 @@ fn merge_times(times_a: &[(Instant, Duration)], times_b: &[(Instant, Duration)])
 
 impl WriteRow for PercentileLatency {
 	fn as_row(&self, row: &mut RowData) -> io::Result<()> {
+		row.push(Stats::OUTPUT_SCHEMA_VERSION);
 		row.push(self.percentile);
 
 		if let Some(latency) = self.ping_latency {
@@ -432,6 +2328,194 @@ impl WriteRow for PercentileLatency {
 			row.push(latency);
 		}
 
+		if let Some(latency) = self.del_latency {
+			row.push(latency);
+		}
+
+		Ok(())
+	}
+}
+
+impl WriteRow for HistogramBucket {
+	fn as_row(&self, row: &mut RowData) -> io::Result<()> {
+		row.push(Stats::OUTPUT_SCHEMA_VERSION);
+		row.push(self.low);
+		row.push(self.high);
+
+		row.push(self.ping_count);
+		row.push(self.get_count);
+		row.push(self.set_count);
+		row.push(self.del_count);
+
+		Ok(())
+	}
+}
+
+impl WriteRow for PercentileWindow {
+	fn as_row(&self, row: &mut RowData) -> io::Result<()> {
+		row.push(Stats::OUTPUT_SCHEMA_VERSION);
+		row.push(self.second);
+
+		for percentiles in [self.get_percentiles, self.set_percentiles] {
+			match percentiles {
+				Some((p50, p99)) => {
+					row.push(p50);
+					row.push(p99);
+				},
+
+				None => {
+					row.push("");
+					row.push("");
+				},
+			}
+		}
+
+		Ok(())
+	}
+}
+
+impl WriteRow for RunSummary {
+	fn as_row(&self, row: &mut RowData) -> io::Result<()> {
+		row.push(Stats::OUTPUT_SCHEMA_VERSION);
+
+		row.push(&self.run_name);
+		row.push(&self.config);
+
+		row.push(self.requests);
+		row.push(self.p50);
+		row.push(self.p99);
+		row.push(self.ops_per_second.round());
+
+		Ok(())
+	}
+}
+
+impl WriteRow for Throughput {
+	fn as_row(&self, row: &mut RowData) -> io::Result<()> {
+		row.push(Stats::OUTPUT_SCHEMA_VERSION);
+		row.push(self.second);
+
+		row.push(self.ping_ops);
+		row.push(self.get_ops);
+		row.push(self.set_ops);
+		row.push(self.del_ops);
+
 		Ok(())
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use std::{
+		mem,
+		sync::Arc,
+		time::{Instant, Duration},
+	};
+
+	use crate::{
+		stats::{Stats, StatsMemory},
+		timer::TimerInstant,
+	};
+
+	const REQUESTS: u64 = 1000;
+
+	#[test]
+	fn sampling_keeps_counts_exact() {
+		for sample_rate in [0.01, 0.1, 0.25, 0.5, 1.0] {
+			let stats = store_requests(Stats::default().with_sample_rate(Some(sample_rate)));
+			let samples = (REQUESTS as f64 * sample_rate).ceil() as usize;
+
+			assert_eq!(stats.ping_count, REQUESTS);
+			assert_eq!(stats.get_count, REQUESTS);
+			assert_eq!(stats.set_count, REQUESTS);
+			assert_eq!(stats.del_count, REQUESTS);
+			assert_eq!(stats.sets_with_ttl, REQUESTS);
+
+			assert_eq!(stats.ping_latencies.len(), samples);
+			assert_eq!(stats.get_latencies.len(), samples);
+			assert_eq!(stats.set_latencies.len(), samples);
+			assert_eq!(stats.del_latencies.len(), samples);
+			assert_eq!(stats.set_ttls.len(), samples);
+		}
+	}
+
+	#[test]
+	fn sampling_spreads_samples_evenly() {
+		let stats = Stats::default().with_sample_rate(Some(0.25));
+
+		let sampled = (0..REQUESTS)
+			.filter(|index| stats.should_sample(*index))
+			.collect::<Vec<_>>();
+
+		assert!(sampled.windows(2).all(|indices| indices[1] - indices[0] == 4));
+	}
+
+	#[test]
+	fn no_sample_rate_stores_every_latency() {
+		let stats = store_requests(Stats::default());
+
+		assert_eq!(stats.get_count, REQUESTS);
+		assert_eq!(stats.get_latencies.len(), REQUESTS as usize);
+	}
+
+	#[test]
+	fn streaming_stores_no_latencies() {
+		let stats = store_requests(Stats::default().with_streaming_percentiles(true));
+
+		assert_eq!(stats.get_count, REQUESTS);
+		assert!(stats.get_latencies.is_empty());
+		assert!(stats.set_ttls.is_empty());
+		assert_eq!(stats.get_get_distribution().len(), REQUESTS as usize);
+	}
+
+	#[test]
+	fn sampled_latencies_are_tracked_in_stats_memory() {
+		let stats_memory = Arc::new(StatsMemory::new(u64::MAX));
+
+		store_requests(Stats::default()
+			.with_sample_rate(Some(0.1))
+			.with_stats_memory(Some(stats_memory.clone())));
+
+		// four latencies and a TTL are stored per sampled request
+		let samples = (REQUESTS as f64 * 0.1).ceil() as usize;
+		let bytes = samples * (mem::size_of::<(Instant, Duration)>() * 4 + mem::size_of::<f64>());
+
+		assert_eq!(stats_memory.bytes(), bytes as u64);
+	}
+
+	#[test]
+	fn stats_memory_counts_keys_until_released() {
+		let stats_memory = StatsMemory::new(100);
+
+		stats_memory.store_key("key", 8);
+		assert!(stats_memory.bytes() > 0);
+		assert!(!stats_memory.is_exceeded());
+
+		stats_memory.release_key("key", 8);
+		assert_eq!(stats_memory.bytes(), 0);
+
+		stats_memory.store_key(&"k".repeat(100), 0);
+		assert!(stats_memory.is_exceeded());
+	}
+
+	#[test]
+	fn merged_stats_add_counts() {
+		let mut stats = store_requests(Stats::default().with_sample_rate(Some(0.1)));
+		stats += store_requests(Stats::default().with_sample_rate(Some(0.1)));
+
+		assert_eq!(stats.get_count, REQUESTS * 2);
+		assert_eq!(stats.get_latencies.len(), (REQUESTS as f64 * 0.1).ceil() as usize * 2);
+	}
+
+	fn store_requests(mut stats: Stats) -> Stats {
+		for _ in 0..REQUESTS {
+			stats.store_ping_time(TimerInstant::from(Instant::now()));
+			stats.store_get_time(TimerInstant::from(Instant::now()), "key");
+			stats.store_set_time(TimerInstant::from(Instant::now()));
+			stats.store_set_ttl(Some(Duration::from_secs(60)));
+			stats.store_del_time(TimerInstant::from(Instant::now()));
+		}
+
+		stats
+	}
+}
@@ -0,0 +1,213 @@
+/*
+ * Copyright (c) Kia Shakiba
+ *
+ * This source code is licensed under the GNU AGPLv3 license found in the
+ * LICENSE file in the root directory of this source tree.
+ */
+
+use std::{
+	io,
+	fs::File,
+	path::Path,
+	time::Duration,
+};
+
+use clap::ValueEnum;
+use rand::{Rng, SeedableRng, rngs::StdRng};
+use rand_distr::{Distribution, Zipf};
+use kwik::file::{
+	FileWriter,
+	binary::BinaryWriter,
+};
+
+use crate::{
+	access::{Access, Command},
+	trace::{self, TraceFormat},
+};
+
+/// Generates GET and SET accesses over a fixed key space in place of a
+/// trace. Keys are chosen uniformly unless another distribution is set.
+pub struct SyntheticWorkload {
+	keys: u64,
+	read_ratio: f64,
+	value_size: u32,
+
+	remaining: u64,
+	timestamp: u64,
+	interval: u64,
+	ttl: Option<Duration>,
+
+	key_distribution: KeyDistribution,
+	rng: StdRng,
+}
+
+#[derive(Debug, Copy, Clone, ValueEnum)]
+pub enum KeyDistributionType {
+	/// Every key is equally likely.
+	Uniform,
+
+	/// Lower keys are accessed more often, following a Zipfian distribution.
+	Zipf,
+
+	/// A fraction of the traffic goes to a small set of hot keys.
+	HotSet,
+}
+
+/// How the keys of synthetic accesses are chosen from the key space.
+pub enum KeyDistribution {
+	Uniform,
+	Zipf(Zipf<f64>),
+
+	HotSet {
+		hot_keys: u64,
+		hot_traffic: f64,
+	},
+}
+
+impl SyntheticWorkload {
+	pub fn new(keys: u64, read_ratio: f64, value_size: u32, requests: u64) -> Self {
+		assert!(keys > 0, "Key space cannot be empty.");
+		assert!((0.0..=1.0).contains(&read_ratio), "Read ratio must be in [0, 1].");
+
+		SyntheticWorkload {
+			keys,
+			read_ratio,
+			value_size,
+
+			remaining: requests,
+			timestamp: 0,
+			interval: 1,
+			ttl: None,
+
+			key_distribution: KeyDistribution::Uniform,
+			rng: StdRng::from_os_rng(),
+		}
+	}
+
+	pub fn with_key_distribution(mut self, key_distribution: KeyDistribution) -> Self {
+		self.key_distribution = key_distribution;
+		self
+	}
+
+	/// Seeds the random number generator so that the same accesses are
+	/// generated across runs.
+	pub fn with_seed(mut self, seed: Option<u64>) -> Self {
+		if let Some(seed) = seed {
+			self.rng = StdRng::seed_from_u64(seed);
+		}
+
+		self
+	}
+
+	/// Sets the number of milliseconds between the timestamps of
+	/// consecutive accesses.
+	pub fn with_interval(mut self, interval: u64) -> Self {
+		self.interval = interval;
+		self
+	}
+
+	/// Sets the TTL of every SET.
+	pub fn with_ttl(mut self, ttl: Option<Duration>) -> Self {
+		self.ttl = ttl;
+		self
+	}
+
+	/// Writes every access to a v1 binary trace file with a header,
+	/// returning the number of accesses written.
+	pub fn save_trace<P>(self, path: P) -> io::Result<u64>
+	where
+		P: AsRef<Path>,
+	{
+		let mut file = File::create(path)?;
+		trace::write_header(&mut file, TraceFormat::V1)?;
+
+		let mut writer = BinaryWriter::<Access>::from_file(file)?;
+		let mut count = 0;
+
+		for access in self {
+			writer.write_chunk(&access)?;
+			count += 1;
+		}
+
+		writer.flush()?;
+
+		Ok(count)
+	}
+
+	fn next_key(&mut self) -> u64 {
+		self.key_distribution.sample(&mut self.rng, self.keys)
+	}
+}
+
+impl KeyDistribution {
+	/// Creates a Zipfian distribution over the key space with the supplied
+	/// skew, where a skew of zero is uniform.
+	pub fn zipf(keys: u64, skew: f64) -> Self {
+		let zipf = Zipf::new(keys as f64, skew)
+			.expect("Invalid Zipfian skew.");
+
+		KeyDistribution::Zipf(zipf)
+	}
+
+	/// Creates a distribution which sends `hot_traffic` of the accesses to
+	/// the lowest `hot_keys` fraction of the key space, and the rest to the
+	/// remaining keys.
+	pub fn hot_set(keys: u64, hot_keys: f64, hot_traffic: f64) -> Self {
+		assert!((0.0..=1.0).contains(&hot_keys), "Hot key fraction must be in [0, 1].");
+		assert!((0.0..=1.0).contains(&hot_traffic), "Hot traffic fraction must be in [0, 1].");
+
+		KeyDistribution::HotSet {
+			hot_keys: ((keys as f64 * hot_keys).ceil() as u64).clamp(1, keys),
+			hot_traffic,
+		}
+	}
+
+	fn sample(&self, rng: &mut StdRng, keys: u64) -> u64 {
+		match self {
+			KeyDistribution::Uniform => rng.random_range(0..keys),
+
+			// the Zipfian distribution samples ranks starting from one
+			KeyDistribution::Zipf(zipf) => zipf.sample(rng) as u64 - 1,
+
+			KeyDistribution::HotSet { hot_keys, hot_traffic } => {
+				if *hot_keys == keys || rng.random_bool(*hot_traffic) {
+					rng.random_range(0..*hot_keys)
+				} else {
+					rng.random_range(*hot_keys..keys)
+				}
+			},
+		}
+	}
+}
+
+impl Iterator for SyntheticWorkload {
+	type Item = Access;
+
+	fn next(&mut self) -> Option<Self::Item> {
+		if self.remaining == 0 {
+			return None;
+		}
+
+		self.remaining -= 1;
+		self.timestamp += self.interval;
+
+		let key = self.next_key().to_string();
+
+		let (command, value, ttl) = match self.rng.random_bool(self.read_ratio) {
+			true => (Command::Get, Box::default(), None),
+			false => (Command::Set, vec![0u8; self.value_size as usize].into(), self.ttl),
+		};
+
+		let access = Access {
+			timestamp: self.timestamp,
+			command,
+
+			key,
+			value,
+
+			ttl,
+		};
+
+		Some(access)
+	}
+}
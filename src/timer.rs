@@ -0,0 +1,94 @@
+/*
+ * Copyright (c) Kia Shakiba
+ *
+ * This source code is licensed under the GNU AGPLv3 license found in the
+ * LICENSE file in the root directory of this source tree.
+ */
+
+use std::{
+	fmt::Debug,
+	sync::Arc,
+	time::{Instant, Duration},
+};
+
+use clap::ValueEnum;
+use quanta::Clock;
+
+/// A source of time used to measure request latencies.
+pub trait Timer: Debug + Send + Sync {
+	fn now(&self) -> TimerInstant;
+	fn elapsed(&self, start: TimerInstant) -> Duration;
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, ValueEnum)]
+pub enum TimerType {
+	/// The standard library's monotonic clock.
+	Std,
+
+	/// A TSC-based clock where available, which has a finer resolution on
+	/// fast local benchmarks.
+	Quanta,
+}
+
+/// When a request started. The instant places the request on the run's
+/// timeline, while its latency is measured from the timer's own reading.
+#[derive(Debug, Copy, Clone)]
+pub struct TimerInstant {
+	pub instant: Instant,
+
+	// readings taken from instants which did not come from the timer, such
+	// as scheduled instants, fall back to the standard clock
+	raw: Option<u64>,
+}
+
+#[derive(Debug, Default)]
+pub struct StdTimer;
+
+#[derive(Debug, Default)]
+pub struct QuantaTimer {
+	clock: Clock,
+}
+
+impl TimerType {
+	pub fn timer(&self) -> Arc<dyn Timer> {
+		match self {
+			TimerType::Std => Arc::new(StdTimer),
+			TimerType::Quanta => Arc::new(QuantaTimer::default()),
+		}
+	}
+}
+
+impl Timer for StdTimer {
+	fn now(&self) -> TimerInstant {
+		TimerInstant::from(Instant::now())
+	}
+
+	fn elapsed(&self, start: TimerInstant) -> Duration {
+		start.instant.elapsed()
+	}
+}
+
+impl Timer for QuantaTimer {
+	fn now(&self) -> TimerInstant {
+		TimerInstant {
+			instant: Instant::now(),
+			raw: Some(self.clock.raw()),
+		}
+	}
+
+	fn elapsed(&self, start: TimerInstant) -> Duration {
+		match start.raw {
+			Some(raw) => self.clock.delta(raw, self.clock.raw()),
+			None => start.instant.elapsed(),
+		}
+	}
+}
+
+impl From<Instant> for TimerInstant {
+	fn from(instant: Instant) -> Self {
+		TimerInstant {
+			instant,
+			raw: None,
+		}
+	}
+}
@@ -0,0 +1,1066 @@
+/*
+ * Copyright (c) Kia Shakiba
+ *
+ * This source code is licensed under the GNU AGPLv3 license found in the
+ * LICENSE file in the root directory of this source tree.
+ */
+
+use std::{
+	fmt::{self, Display},
+	fs::File,
+	path::Path,
+	collections::{HashMap, HashSet},
+	io::{self, BufReader, Cursor, Read, Write, Seek, SeekFrom},
+	time::Duration,
+};
+
+use byteorder::{LittleEndian, ReadBytesExt};
+use clap::ValueEnum;
+use crc32fast::Hasher;
+use flate2::read::MultiGzDecoder;
+use kwik::file::binary::{SizedChunk, ReadChunk};
+use crate::access::{Access, Command};
+
+// the magic number at the start of a trace file with a header, which is
+// followed by the trace format's version as a little-endian u32. Files
+// without a header are legacy traces whose format must be supplied
+const TRACE_MAGIC: [u8; 8] = *b"PAPERTRC";
+const TRACE_HEADER_SIZE: usize = 12;
+
+// the size of a v3 record excluding its key and value
+const V3_HEADER_SIZE: usize = 19;
+
+// the size of a v4 record excluding its key and value
+const V4_HEADER_SIZE: usize = 23;
+
+// the size of a v5 record excluding its key and value
+const V5_HEADER_SIZE: usize = 27;
+
+#[derive(Debug, Copy, Clone, PartialEq, ValueEnum)]
+pub enum TraceFormat {
+	/// Fixed-size records with zero-filled values.
+	V1,
+
+	/// Fixed-size record headers followed by the value bytes.
+	V2,
+
+	/// Length-prefixed UTF-8 keys followed by the value bytes.
+	V3,
+
+	/// The v3 format with 64-bit TTLs in milliseconds.
+	V4,
+
+	/// The v4 format with a CRC32 of each record appended to it.
+	V5,
+}
+
+/// The unit of the TTLs stored in a trace.
+#[derive(Debug, Copy, Clone, PartialEq, ValueEnum)]
+pub enum TtlUnit {
+	/// Seconds.
+	S,
+
+	/// Milliseconds.
+	Ms,
+}
+
+/// The compression of a trace file, detected from its extension.
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum Compression {
+	None,
+	Gzip,
+	Zstd,
+}
+
+/// An opened trace file positioned at its first record.
+struct TraceFile {
+	reader: Box<dyn Read>,
+
+	// the size of the records if it is known
+	size: Option<u64>,
+
+	// the format named by the file's header, if it has one
+	header: Option<TraceFormat>,
+}
+
+pub struct TraceReader {
+	reader: CountingReader,
+	format: TraceFormat,
+
+	size: Option<u64>,
+	header_size: u64,
+
+	buf: Box<[u8]>,
+	count: u64,
+}
+
+/// Counts the bytes read through it, so that the end of the trace can be
+/// told apart from a record which is cut off partway through.
+struct CountingReader {
+	reader: Box<dyn Read>,
+	count: u64,
+}
+
+impl TraceReader {
+	pub fn from_path<P>(path: P, format: TraceFormat) -> io::Result<Self>
+	where
+		P: AsRef<Path>,
+	{
+		let trace_file = open_file(path)?;
+		check_header(trace_file.header, format)?;
+
+		let mut trace_reader = TraceReader::new(trace_file.reader, format, trace_file.size);
+
+		if trace_file.header.is_some() {
+			trace_reader.header_size = TRACE_HEADER_SIZE as u64;
+		}
+
+		Ok(trace_reader)
+	}
+
+	/// Creates a reader which processes the supplied trace files in order,
+	/// as though they were a single trace.
+	pub fn from_paths<P>(paths: &[P], format: TraceFormat) -> io::Result<Self>
+	where
+		P: AsRef<Path>,
+	{
+		let mut reader: Box<dyn Read> = Box::new(io::empty());
+		let mut size = Some(0);
+
+		for path in paths {
+			let trace_file = open_file(path)?;
+			check_header(trace_file.header, format)?;
+
+			// the size is only known if no file is compressed
+			size = size.zip(trace_file.size).map(|(size, file_size)| size + file_size);
+			reader = Box::new(reader.chain(trace_file.reader));
+		}
+
+		Ok(TraceReader::new(reader, format, size))
+	}
+
+	/// Creates a reader which streams the trace from stdin. The size of
+	/// the trace is not known ahead of time. Since the header cannot be
+	/// read before the trace is opened, the format it names takes
+	/// precedence over the supplied one, which only applies to legacy
+	/// traces without a header.
+	pub fn from_stdin(format: TraceFormat) -> io::Result<Self> {
+		let (header, reader) = read_header(Box::new(BufReader::new(io::stdin())))?;
+
+		Ok(TraceReader::new(reader, header.unwrap_or(format), None))
+	}
+
+	fn new(reader: impl Read + 'static, format: TraceFormat, size: Option<u64>) -> Self {
+		TraceReader {
+			reader: CountingReader {
+				reader: Box::new(reader),
+				count: 0,
+			},
+
+			format,
+
+			size,
+			header_size: 0,
+
+			buf: vec![0; Access::chunk_size()].into_boxed_slice(),
+			count: 0,
+		}
+	}
+
+	pub fn format(&self) -> TraceFormat {
+		self.format
+	}
+
+	/// Returns the size of the trace's records in bytes if it is known,
+	/// which it is not for traces from stdin or compressed traces.
+	pub fn size(&self) -> Option<u64> {
+		self.size
+	}
+
+	/// Returns the size of the header at the start of a single trace file,
+	/// or zero if it has none.
+	pub fn header_size(&self) -> u64 {
+		self.header_size
+	}
+
+	/// Returns the number of accesses in the trace if it can be determined
+	/// without reading the whole trace.
+	pub fn len(&self) -> Option<u64> {
+		match self.format {
+			TraceFormat::V1 => self.size.map(|size| size / Access::chunk_size() as u64),
+			TraceFormat::V2 | TraceFormat::V3 | TraceFormat::V4 | TraceFormat::V5 => None,
+		}
+	}
+
+	/// Reads the next access. Reaching the end of the trace between records
+	/// is an `UnexpectedEof` error, while reaching it partway through a
+	/// record is an `InvalidData` error.
+	pub fn read_access(&mut self) -> io::Result<Access> {
+		let start = self.reader.count;
+
+		let access = match self.read_record() {
+			Ok(access) => access,
+
+			Err(err) if err.kind() == io::ErrorKind::UnexpectedEof && self.reader.count > start => {
+				return Err(io::Error::new(
+					io::ErrorKind::InvalidData,
+					"Truncated record.",
+				));
+			},
+
+			Err(err) => return Err(err),
+		};
+
+		self.count += 1;
+
+		Ok(access)
+	}
+
+	fn read_record(&mut self) -> io::Result<Access> {
+		let access = match self.format {
+			TraceFormat::V1 | TraceFormat::V2 => {
+				self.reader.read_exact(&mut self.buf)?;
+
+				let mut access = Access::from_chunk(&self.buf)?;
+
+				if self.format == TraceFormat::V2 {
+					// the value buffer is already allocated to the record's value
+					// size, so the actual bytes can be read straight into it
+					self.reader.read_exact(&mut access.value)?;
+				}
+
+				access
+			},
+
+			TraceFormat::V3 | TraceFormat::V4 | TraceFormat::V5 => self.read_string_key_access()?,
+		};
+
+		Ok(access)
+	}
+
+	fn read_string_key_access(&mut self) -> io::Result<Access> {
+		let timestamp = self.reader.read_u64::<LittleEndian>()?;
+
+		let command_byte = self.reader.read_u8()?;
+
+		let key_size = self.reader.read_u16::<LittleEndian>()?;
+		let mut key = vec![0u8; key_size as usize];
+		self.reader.read_exact(&mut key)?;
+
+		let value_size = self.reader.read_u32::<LittleEndian>()?;
+
+		let ttl = match self.format {
+			TraceFormat::V4 | TraceFormat::V5 => match self.reader.read_u64::<LittleEndian>()? {
+				0 => None,
+				ttl => Some(Duration::from_millis(ttl)),
+			},
+
+			_ => match self.reader.read_u32::<LittleEndian>()? {
+				0 => None,
+				ttl => Some(Duration::from_secs(ttl.into())),
+			},
+		};
+
+		let mut value = vec![0u8; value_size as usize];
+		self.reader.read_exact(&mut value)?;
+
+		if self.format == TraceFormat::V5 {
+			let checksum = self.reader.read_u32::<LittleEndian>()?;
+
+			let mut hasher = Hasher::new();
+			hasher.update(&timestamp.to_le_bytes());
+			hasher.update(&[command_byte]);
+			hasher.update(&key_size.to_le_bytes());
+			hasher.update(&key);
+			hasher.update(&value_size.to_le_bytes());
+			hasher.update(&ttl.map_or(0, |ttl| ttl.as_millis() as u64).to_le_bytes());
+			hasher.update(&value);
+
+			if hasher.finalize() != checksum {
+				return Err(io::Error::new(
+					io::ErrorKind::InvalidData,
+					"Checksum mismatch.",
+				));
+			}
+		}
+
+		// the record is only parsed once its checksum has been verified so
+		// that corruption is always reported as such
+		let command = Command::from_byte(command_byte)?;
+
+		let key = String::from_utf8(key)
+			.map_err(|_| io::Error::new(
+				io::ErrorKind::InvalidData,
+				"Invalid access key.",
+			))?;
+
+		let access = Access {
+			timestamp,
+			command,
+
+			key,
+			value: value.into(),
+
+			ttl,
+		};
+
+		Ok(access)
+	}
+}
+
+impl TraceFormat {
+	/// Returns the number of bytes the supplied access occupies in a trace
+	/// file of this format.
+	pub fn record_size(&self, access: &Access) -> usize {
+		match self {
+			TraceFormat::V1 => Access::chunk_size(),
+			TraceFormat::V2 => Access::chunk_size() + access.value.len(),
+			TraceFormat::V3 => V3_HEADER_SIZE + access.key.len() + access.value.len(),
+			TraceFormat::V4 => V4_HEADER_SIZE + access.key.len() + access.value.len(),
+			TraceFormat::V5 => V5_HEADER_SIZE + access.key.len() + access.value.len(),
+		}
+	}
+
+	/// Returns the version of this format written to trace headers.
+	fn version(&self) -> u32 {
+		match self {
+			TraceFormat::V1 => 1,
+			TraceFormat::V2 => 2,
+			TraceFormat::V3 => 3,
+			TraceFormat::V4 => 4,
+			TraceFormat::V5 => 5,
+		}
+	}
+
+	fn from_version(version: u32) -> io::Result<Self> {
+		match version {
+			1 => Ok(TraceFormat::V1),
+			2 => Ok(TraceFormat::V2),
+			3 => Ok(TraceFormat::V3),
+			4 => Ok(TraceFormat::V4),
+			5 => Ok(TraceFormat::V5),
+
+			_ => Err(io::Error::new(
+				io::ErrorKind::InvalidData,
+				format!("Unsupported trace format version {version} in trace header."),
+			)),
+		}
+	}
+
+	/// Returns the unit the TTLs of this format are read in.
+	pub fn ttl_unit(&self) -> TtlUnit {
+		match self {
+			TraceFormat::V4 | TraceFormat::V5 => TtlUnit::Ms,
+			_ => TtlUnit::S,
+		}
+	}
+}
+
+impl TtlUnit {
+	/// Reinterprets a TTL which was read in the `from` unit as a TTL in
+	/// this unit.
+	pub fn reinterpret(&self, ttl: Duration, from: TtlUnit) -> Duration {
+		let raw = match from {
+			TtlUnit::S => ttl.as_secs(),
+			TtlUnit::Ms => ttl.as_millis() as u64,
+		};
+
+		match self {
+			TtlUnit::S => Duration::from_secs(raw),
+			TtlUnit::Ms => Duration::from_millis(raw),
+		}
+	}
+}
+
+impl Read for CountingReader {
+	fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+		let size = self.reader.read(buf)?;
+		self.count += size as u64;
+
+		Ok(size)
+	}
+}
+
+impl Iterator for TraceReader {
+	type Item = Access;
+
+	fn next(&mut self) -> Option<Self::Item> {
+		match self.read_access() {
+			Ok(access) => Some(access),
+			Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => None,
+
+			Err(_) => panic!(
+				"An error occurred on access {} when reading trace.",
+				self.count + 1,
+			),
+		}
+	}
+}
+
+/// Reads through the whole trace file, checking that every record can be
+/// parsed and that timestamps never decrease. Returns the number of accesses
+/// or an error describing the first invalid record.
+pub fn validate<P>(path: P, format: TraceFormat) -> io::Result<u64>
+where
+	P: AsRef<Path>,
+{
+	scan(path, format, true)
+}
+
+/// Reads through the whole trace file, checking the checksum of every
+/// record. Returns the number of accesses or an error describing the first
+/// corrupted record.
+pub fn verify_checksums<P>(path: P, format: TraceFormat) -> io::Result<u64>
+where
+	P: AsRef<Path>,
+{
+	if format != TraceFormat::V5 {
+		return Err(io::Error::new(
+			io::ErrorKind::InvalidInput,
+			format!("Trace format {format} has no checksums."),
+		));
+	}
+
+	scan(path, format, false)
+}
+
+fn scan<P>(path: P, format: TraceFormat, check_order: bool) -> io::Result<u64>
+where
+	P: AsRef<Path>,
+{
+	let mut reader = TraceReader::from_path(path, format)?;
+	let size = reader.size().map(|size| size + reader.header_size());
+
+	let mut offset = reader.header_size();
+	let mut count = 0u64;
+	let mut prev_timestamp: Option<u64> = None;
+
+	loop {
+		let access = match reader.read_access() {
+			Ok(access) => access,
+
+			Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => {
+				if size.is_some_and(|size| size != offset) {
+					return Err(io::Error::new(
+						io::ErrorKind::InvalidData,
+						format!("Truncated record {count} at byte offset {offset}."),
+					));
+				}
+
+				return Ok(count);
+			},
+
+			Err(err) => return Err(io::Error::new(
+				err.kind(),
+				format!("{err} Found in record {count} at byte offset {offset}."),
+			)),
+		};
+
+		if check_order && prev_timestamp.is_some_and(|timestamp| timestamp > access.timestamp) {
+			return Err(io::Error::new(
+				io::ErrorKind::InvalidData,
+				format!("Invalid timestamp order in record {count} at byte offset {offset}."),
+			));
+		}
+
+		prev_timestamp = Some(access.timestamp);
+
+		offset += format.record_size(&access) as u64;
+		count += 1;
+	}
+}
+
+/// Returns the `count` most frequently accessed keys across the trace files.
+pub fn get_hot_keys<P>(paths: &[P], format: TraceFormat, count: usize) -> io::Result<HashSet<String>>
+where
+	P: AsRef<Path>,
+{
+	let mut frequencies = HashMap::<String, u64>::new();
+
+	for access in TraceReader::from_paths(paths, format)? {
+		*frequencies.entry(access.key).or_default() += 1;
+	}
+
+	let mut frequencies = frequencies
+		.into_iter()
+		.collect::<Vec<_>>();
+
+	frequencies.sort_unstable_by(|(_, a), (_, b)| b.cmp(a));
+
+	let hot_keys = frequencies
+		.into_iter()
+		.take(count)
+		.map(|(key, _)| key)
+		.collect();
+
+	Ok(hot_keys)
+}
+
+/// Returns the format named by the headers of the trace files, or `None`
+/// if none of them have a header. Files with and without a header cannot
+/// be mixed, since the legacy files would be read in the header's format.
+pub fn read_format<P>(paths: &[P]) -> io::Result<Option<TraceFormat>>
+where
+	P: AsRef<Path>,
+{
+	let mut format: Option<TraceFormat> = None;
+	let mut legacy_path: Option<&Path> = None;
+
+	for path in paths {
+		let header = open_file(path)?.header;
+
+		match (format, header) {
+			(Some(format), Some(header)) if format != header => {
+				return Err(io::Error::new(
+					io::ErrorKind::InvalidData,
+					format!("The trace files have headers for both {format} and {header}."),
+				));
+			},
+
+			(None, Some(header)) => format = Some(header),
+			(_, None) => legacy_path = legacy_path.or(Some(path.as_ref())),
+			_ => {},
+		}
+	}
+
+	if let Some((format, legacy_path)) = format.zip(legacy_path) {
+		return Err(io::Error::new(
+			io::ErrorKind::InvalidData,
+			format!(
+				"The trace file {} has no header, but the other trace files have headers for {format}.",
+				legacy_path.display(),
+			),
+		));
+	}
+
+	Ok(format)
+}
+
+/// Writes a header naming the format to the start of a trace file.
+pub fn write_header<W>(writer: &mut W, format: TraceFormat) -> io::Result<()>
+where
+	W: Write,
+{
+	writer.write_all(&TRACE_MAGIC)?;
+	writer.write_all(&format.version().to_le_bytes())
+}
+
+/// Returns the time between the first access of the first trace file and
+/// the last access of the last trace file. Timestamps may go back in time
+/// by at most `allow_skew` milliseconds, both within and across files.
+pub fn get_timespan<P>(paths: &[P], format: TraceFormat, allow_skew: u64) -> io::Result<u64>
+where
+	P: AsRef<Path>,
+{
+	let mut first_timestamp: Option<u64> = None;
+	let mut last_timestamp: Option<u64> = None;
+
+	for path in paths {
+		let path = path.as_ref();
+		let (file_first_timestamp, file_last_timestamp) = get_timestamp_bounds(path, format)?;
+
+		// timestamps must keep increasing across file boundaries
+		if let Some(timestamp) = last_timestamp
+			&& timestamp.saturating_sub(file_first_timestamp) > allow_skew
+		{
+			return Err(invalid_timestamp_order(format!(
+				"The trace file {} starts at timestamp {file_first_timestamp}, before the previous file ends at {timestamp}.",
+				path.display(),
+			)));
+		}
+
+		if file_first_timestamp.saturating_sub(file_last_timestamp) > allow_skew {
+			return Err(invalid_timestamp_order(format!(
+				"The trace file {} starts at timestamp {file_first_timestamp}, after it ends at {file_last_timestamp}.",
+				path.display(),
+			)));
+		}
+
+		first_timestamp.get_or_insert(file_first_timestamp);
+
+		// tolerated backward jumps are sent at the previous timestamp, so
+		// the trace ends at the latest timestamp seen
+		last_timestamp = Some(last_timestamp.map_or(file_last_timestamp, |timestamp| timestamp.max(file_last_timestamp)));
+	}
+
+	match first_timestamp.zip(last_timestamp) {
+		Some((first_timestamp, last_timestamp)) => Ok(last_timestamp.saturating_sub(first_timestamp)),
+		None => Ok(0),
+	}
+}
+
+fn get_timestamp_bounds<P>(path: P, format: TraceFormat) -> io::Result<(u64, u64)>
+where
+	P: AsRef<Path>,
+{
+	let bounds = match format {
+		TraceFormat::V1 if Compression::from_path(&path) == Compression::None => {
+			let mut file = File::open(path)?;
+			let mut buf = vec![0; Access::chunk_size()];
+
+			// the header is read through a clone which shares the file's
+			// position, so the position is reset past the header after
+			let (header, _) = read_header(Box::new(file.try_clone()?))?;
+			let header_size = header.map_or(0, |_| TRACE_HEADER_SIZE as u64);
+
+			file.seek(SeekFrom::Start(header_size))?;
+			file.read_exact(&mut buf)?;
+			let first_access = Access::from_chunk(&buf)?;
+
+			file.seek(SeekFrom::End(-(Access::chunk_size() as i64)))?;
+			file.read_exact(&mut buf)?;
+			let last_access = Access::from_chunk(&buf)?;
+
+			(first_access.timestamp, last_access.timestamp)
+		},
+
+		// records are variable-length or compressed so the last one can
+		// only be found by reading through the whole trace
+		_ => {
+			let mut reader = TraceReader::from_path(path, format)?;
+			let first_timestamp = reader.read_access()?.timestamp;
+
+			let last_timestamp = reader
+				.map(|access| access.timestamp)
+				.last()
+				.unwrap_or(first_timestamp);
+
+			(first_timestamp, last_timestamp)
+		},
+	};
+
+	Ok(bounds)
+}
+
+impl Compression {
+	fn from_path<P>(path: P) -> Self
+	where
+		P: AsRef<Path>,
+	{
+		let extension = path
+			.as_ref()
+			.extension()
+			.and_then(|extension| extension.to_str());
+
+		match extension {
+			Some("gz") => Compression::Gzip,
+			Some("zst" | "zstd") => Compression::Zstd,
+			_ => Compression::None,
+		}
+	}
+}
+
+/// Opens a trace file, decompressing it as it is read if it is compressed.
+fn open_file<P>(path: P) -> io::Result<TraceFile>
+where
+	P: AsRef<Path>,
+{
+	let compression = Compression::from_path(&path);
+	let file = File::open(path)?;
+
+	let (reader, size): (Box<dyn Read>, Option<u64>) = match compression {
+		Compression::None => {
+			let size = file.metadata()?.len();
+			(Box::new(BufReader::new(file)), Some(size))
+		},
+
+		Compression::Gzip => {
+			let decoder = MultiGzDecoder::new(BufReader::new(file));
+			(Box::new(BufReader::new(decoder)), None)
+		},
+
+		Compression::Zstd => {
+			let decoder = zstd::Decoder::new(file)?;
+			(Box::new(BufReader::new(decoder)), None)
+		},
+	};
+
+	let (header, reader) = read_header(reader)?;
+	let size = size.map(|size| size - header.map_or(0, |_| TRACE_HEADER_SIZE as u64));
+
+	let trace_file = TraceFile {
+		reader,
+		size,
+		header,
+	};
+
+	Ok(trace_file)
+}
+
+/// Reads the header from the start of a trace, returning the format it
+/// names along with a reader positioned at the first record. Traces without
+/// a header are read from the start.
+fn read_header(mut reader: Box<dyn Read>) -> io::Result<(Option<TraceFormat>, Box<dyn Read>)> {
+	let mut prefix = Vec::with_capacity(TRACE_HEADER_SIZE);
+
+	(&mut reader)
+		.take(TRACE_HEADER_SIZE as u64)
+		.read_to_end(&mut prefix)?;
+
+	if prefix.len() < TRACE_HEADER_SIZE || prefix[..TRACE_MAGIC.len()] != TRACE_MAGIC {
+		return Ok((None, Box::new(Cursor::new(prefix).chain(reader))));
+	}
+
+	let version = Cursor::new(&prefix[TRACE_MAGIC.len()..]).read_u32::<LittleEndian>()?;
+	let format = TraceFormat::from_version(version)?;
+
+	Ok((Some(format), reader))
+}
+
+fn invalid_timestamp_order(message: String) -> io::Error {
+	io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+fn check_header(header: Option<TraceFormat>, format: TraceFormat) -> io::Result<()> {
+	match header {
+		Some(header) if header != format => Err(io::Error::new(
+			io::ErrorKind::InvalidData,
+			format!("The trace header is for format {header}, not {format}."),
+		)),
+
+		_ => Ok(()),
+	}
+}
+
+impl Display for TraceFormat {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		let s = match self {
+			TraceFormat::V1 => "v1",
+			TraceFormat::V2 => "v2",
+			TraceFormat::V3 => "v3",
+			TraceFormat::V4 => "v4",
+			TraceFormat::V5 => "v5",
+		};
+
+		write!(f, "{s}")
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::{
+		fs::{self, File},
+		io::{self, Cursor, Write},
+		path::PathBuf,
+		process,
+		time::Duration,
+	};
+
+	use crc32fast::Hasher;
+	use flate2::{Compression as GzCompression, write::GzEncoder};
+	use rand::{Rng, SeedableRng, rngs::StdRng};
+	use crate::access::{Access, Command};
+	use crate::trace::{self, TraceFormat, TraceReader, TRACE_HEADER_SIZE};
+
+	const FORMATS: [TraceFormat; 5] = [
+		TraceFormat::V1,
+		TraceFormat::V2,
+		TraceFormat::V3,
+		TraceFormat::V4,
+		TraceFormat::V5,
+	];
+
+	#[test]
+	fn every_format_round_trips() {
+		for format in FORMATS {
+			let accesses = get_accesses(format, 1000);
+			let mut buf = Vec::new();
+
+			for access in &accesses {
+				let size = buf.len();
+				encode(&mut buf, access, format);
+
+				assert_eq!(buf.len() - size, format.record_size(access));
+			}
+
+			let reader = TraceReader::new(Cursor::new(buf), format, None);
+			assert_accesses(reader, &accesses);
+		}
+	}
+
+	#[test]
+	fn header_round_trips() {
+		for format in FORMATS {
+			let accesses = get_accesses(format, 100);
+			let path = get_temp_path(&format!("header-{format}.bin"));
+
+			let mut buf = Vec::new();
+			trace::write_header(&mut buf, format).expect("Could not write header.");
+
+			for access in &accesses {
+				encode(&mut buf, access, format);
+			}
+
+			fs::write(&path, &buf).expect("Could not write trace.");
+
+			assert_eq!(trace::read_format(&[&path]).expect("Could not read format."), Some(format));
+
+			let reader = TraceReader::from_path(&path, format).expect("Could not open trace.");
+			assert_eq!(reader.header_size(), TRACE_HEADER_SIZE as u64);
+			assert_accesses(reader, &accesses);
+
+			fs::remove_file(&path).expect("Could not remove trace.");
+		}
+	}
+
+	#[test]
+	fn header_rejects_other_format() {
+		let path = get_temp_path("header-other.bin");
+
+		let mut buf = Vec::new();
+		trace::write_header(&mut buf, TraceFormat::V4).expect("Could not write header.");
+		fs::write(&path, &buf).expect("Could not write trace.");
+
+		let result = TraceReader::from_path(&path, TraceFormat::V1);
+		fs::remove_file(&path).expect("Could not remove trace.");
+
+		assert!(result.is_err_and(|err| err.kind() == io::ErrorKind::InvalidData));
+	}
+
+	#[test]
+	fn legacy_trace_has_no_format() {
+		let path = get_temp_path("legacy.bin");
+
+		let mut buf = Vec::new();
+		encode(&mut buf, &get_accesses(TraceFormat::V1, 1)[0], TraceFormat::V1);
+		fs::write(&path, &buf).expect("Could not write trace.");
+
+		let format = trace::read_format(&[&path]).expect("Could not read format.");
+		fs::remove_file(&path).expect("Could not remove trace.");
+
+		assert_eq!(format, None);
+	}
+
+	#[test]
+	fn mixed_headers_are_rejected() {
+		let header_path = get_temp_path("mixed-header.bin");
+		let legacy_path = get_temp_path("mixed-legacy.bin");
+
+		let mut buf = Vec::new();
+		trace::write_header(&mut buf, TraceFormat::V1).expect("Could not write header.");
+		fs::write(&header_path, &buf).expect("Could not write trace.");
+		fs::write(&legacy_path, []).expect("Could not write trace.");
+
+		let result = trace::read_format(&[&header_path, &legacy_path]);
+
+		fs::remove_file(&header_path).expect("Could not remove trace.");
+		fs::remove_file(&legacy_path).expect("Could not remove trace.");
+
+		assert!(result.is_err_and(|err| err.kind() == io::ErrorKind::InvalidData));
+	}
+
+	#[test]
+	fn bad_checksum_is_detected() {
+		let accesses = get_accesses(TraceFormat::V5, 10);
+		let mut buf = Vec::new();
+
+		for access in &accesses {
+			encode(&mut buf, access, TraceFormat::V5);
+		}
+
+		// corrupt the timestamp of the second record
+		let offset = TraceFormat::V5.record_size(&accesses[0]);
+		buf[offset] ^= 0xff;
+
+		let path = get_temp_path("bad-checksum.bin");
+		fs::write(&path, &buf).expect("Could not write trace.");
+
+		let result = trace::verify_checksums(&path, TraceFormat::V5);
+		fs::remove_file(&path).expect("Could not remove trace.");
+
+		let err = result.expect_err("Corrupted trace was verified.");
+		assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+		assert!(err.to_string().contains("record 1"));
+	}
+
+	#[test]
+	fn truncated_final_record_is_detected() {
+		for format in FORMATS {
+			let accesses = get_accesses(format, 2);
+			let mut buf = Vec::new();
+
+			for access in &accesses {
+				encode(&mut buf, access, format);
+			}
+
+			buf.truncate(buf.len() - 3);
+
+			let mut reader = TraceReader::new(Cursor::new(buf), format, None);
+			reader.read_access().expect("Could not read the first access.");
+
+			let Err(err) = reader.read_access() else {
+				panic!("Truncated access was read.");
+			};
+
+			assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+		}
+	}
+
+	#[test]
+	fn end_of_trace_is_not_truncation() {
+		let mut reader = TraceReader::new(Cursor::new(Vec::new()), TraceFormat::V3, None);
+
+		let Err(err) = reader.read_access() else {
+			panic!("Access was read from an empty trace.");
+		};
+
+		assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+	}
+
+	#[test]
+	fn gzip_trace_is_decompressed() {
+		let accesses = get_accesses(TraceFormat::V3, 100);
+		let path = get_temp_path("compressed.bin.gz");
+
+		let file = File::create(&path).expect("Could not create trace.");
+		let mut encoder = GzEncoder::new(file, GzCompression::default());
+		encoder.write_all(&encode_trace(&accesses, TraceFormat::V3)).expect("Could not write trace.");
+		encoder.finish().expect("Could not finish trace.");
+
+		let reader = TraceReader::from_path(&path, TraceFormat::V3).expect("Could not open trace.");
+		assert_eq!(reader.size(), None);
+		assert_accesses(reader, &accesses);
+
+		fs::remove_file(&path).expect("Could not remove trace.");
+	}
+
+	#[test]
+	fn zstd_trace_is_decompressed() {
+		let accesses = get_accesses(TraceFormat::V3, 100);
+		let path = get_temp_path("compressed.bin.zst");
+
+		let compressed = zstd::encode_all(Cursor::new(encode_trace(&accesses, TraceFormat::V3)), 0)
+			.expect("Could not compress trace.");
+
+		fs::write(&path, compressed).expect("Could not write trace.");
+
+		let reader = TraceReader::from_path(&path, TraceFormat::V3).expect("Could not open trace.");
+		assert_eq!(reader.size(), None);
+		assert_accesses(reader, &accesses);
+
+		fs::remove_file(&path).expect("Could not remove trace.");
+	}
+
+	/// Returns accesses which can be represented exactly in the format.
+	fn get_accesses(format: TraceFormat, count: usize) -> Vec<Access> {
+		let mut rng = StdRng::seed_from_u64(0);
+
+		(0..count as u64)
+			.map(|timestamp| {
+				let command = match rng.random_range(0..3) {
+					0 => Command::Get,
+					1 => Command::Set,
+					_ => Command::Del,
+				};
+
+				let key = match format {
+					TraceFormat::V1 | TraceFormat::V2 => rng.random::<u64>().to_string(),
+					_ => format!("key-{}", rng.random::<u32>()),
+				};
+
+				let mut value = vec![0u8; rng.random_range(0..256)];
+
+				if format != TraceFormat::V1 {
+					rng.fill(value.as_mut_slice());
+				}
+
+				let ttl = rng.random_bool(0.5).then(|| match format.ttl_unit() {
+					trace::TtlUnit::S => Duration::from_secs(rng.random_range(1..=u32::MAX).into()),
+					trace::TtlUnit::Ms => Duration::from_millis(rng.random_range(1..=u64::from(u32::MAX))),
+				});
+
+				Access {
+					timestamp,
+					command,
+
+					key,
+					value: value.into(),
+
+					ttl,
+				}
+			})
+			.collect()
+	}
+
+	fn encode_trace(accesses: &[Access], format: TraceFormat) -> Vec<u8> {
+		let mut buf = Vec::new();
+
+		for access in accesses {
+			encode(&mut buf, access, format);
+		}
+
+		buf
+	}
+
+	fn encode(buf: &mut Vec<u8>, access: &Access, format: TraceFormat) {
+		let start = buf.len();
+
+		let command_byte: u8 = match access.command {
+			Command::Get => 0,
+			Command::Set => 1,
+			Command::Del => 2,
+		};
+
+		let value_size = access.value.len() as u32;
+
+		buf.extend_from_slice(&access.timestamp.to_le_bytes());
+		buf.push(command_byte);
+
+		match format {
+			TraceFormat::V1 | TraceFormat::V2 => {
+				let key = access.key.parse::<u64>().expect("Key is not numeric.");
+				let ttl = access.ttl.map_or(0, |ttl| ttl.as_secs() as u32);
+
+				buf.extend_from_slice(&key.to_le_bytes());
+				buf.extend_from_slice(&value_size.to_le_bytes());
+				buf.extend_from_slice(&ttl.to_le_bytes());
+			},
+
+			TraceFormat::V3 | TraceFormat::V4 | TraceFormat::V5 => {
+				buf.extend_from_slice(&(access.key.len() as u16).to_le_bytes());
+				buf.extend_from_slice(access.key.as_bytes());
+				buf.extend_from_slice(&value_size.to_le_bytes());
+
+				match format {
+					TraceFormat::V3 => {
+						let ttl = access.ttl.map_or(0, |ttl| ttl.as_secs() as u32);
+						buf.extend_from_slice(&ttl.to_le_bytes());
+					},
+
+					_ => {
+						let ttl = access.ttl.map_or(0, |ttl| ttl.as_millis() as u64);
+						buf.extend_from_slice(&ttl.to_le_bytes());
+					},
+				}
+			},
+		}
+
+		if format != TraceFormat::V1 {
+			buf.extend_from_slice(&access.value);
+		}
+
+		if format == TraceFormat::V5 {
+			let mut hasher = Hasher::new();
+			hasher.update(&buf[start..]);
+
+			buf.extend_from_slice(&hasher.finalize().to_le_bytes());
+		}
+	}
+
+	fn assert_accesses(reader: TraceReader, expected: &[Access]) {
+		let accesses = reader.collect::<Vec<_>>();
+		assert_eq!(accesses.len(), expected.len());
+
+		for (access, expected) in accesses.iter().zip(expected) {
+			assert_eq!(access.timestamp, expected.timestamp);
+			assert!(access.command == expected.command);
+			assert_eq!(access.key, expected.key);
+			assert_eq!(access.value, expected.value);
+			assert_eq!(access.ttl, expected.ttl);
+		}
+	}
+
+	fn get_temp_path(name: &str) -> PathBuf {
+		std::env::temp_dir().join(format!("paper-benchmark-{}-{name}", process::id()))
+	}
+}
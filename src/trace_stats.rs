@@ -0,0 +1,145 @@
+/*
+ * Copyright (c) Kia Shakiba
+ *
+ * This source code is licensed under the GNU AGPLv3 license found in the
+ * LICENSE file in the root directory of this source tree.
+ */
+
+use std::hash::{Hash, Hasher, DefaultHasher};
+use statrs::statistics::{Data, OrderStatistics, Min, Max};
+use kwik::fmt;
+use crate::access::{Access, Command};
+
+// the number of bits of each key hash used to select a register
+const HLL_PRECISION: u32 = 14;
+
+/// Descriptive statistics of a trace, collected without sending any
+/// requests to the server.
+#[derive(Default)]
+pub struct TraceStats {
+	gets: u64,
+	sets: u64,
+	dels: u64,
+
+	value_sizes: Vec<f64>,
+
+	first_timestamp: Option<u64>,
+	last_timestamp: Option<u64>,
+
+	unique_keys: HyperLogLog,
+}
+
+/// Estimates the number of distinct keys in the trace in constant memory.
+struct HyperLogLog {
+	registers: Vec<u8>,
+}
+
+impl TraceStats {
+	pub fn push(&mut self, access: &Access) {
+		match access.command {
+			Command::Get => self.gets += 1,
+			Command::Set => self.sets += 1,
+			Command::Del => self.dels += 1,
+		}
+
+		if access.command == Command::Set {
+			self.value_sizes.push(access.value.len() as f64);
+		}
+
+		self.first_timestamp.get_or_insert(access.timestamp);
+		self.last_timestamp = Some(access.timestamp);
+
+		self.unique_keys.insert(&access.key);
+	}
+
+	pub fn print(&self) {
+		let total = self.gets + self.sets + self.dels;
+
+		println!("\n*** Trace stats ***\n");
+		println!("Accesses:\t{}", fmt::number(total));
+
+		if total == 0 {
+			return;
+		}
+
+		let percent = |count: u64| count as f64 / total as f64 * 100.0;
+
+		println!("GETs:\t\t{} ({:.1}%)", fmt::number(self.gets), percent(self.gets));
+		println!("SETs:\t\t{} ({:.1}%)", fmt::number(self.sets), percent(self.sets));
+		println!("DELs:\t\t{} ({:.1}%)", fmt::number(self.dels), percent(self.dels));
+
+		println!(
+			"Unique keys:\t~{}",
+			fmt::number(self.unique_keys.estimate().round() as u64),
+		);
+
+		if let Some((first_timestamp, last_timestamp)) = self.first_timestamp.zip(self.last_timestamp) {
+			println!(
+				"Timespan:\t{}",
+				fmt::timespan(last_timestamp.saturating_sub(first_timestamp)),
+			);
+		}
+
+		if self.value_sizes.is_empty() {
+			return;
+		}
+
+		let mut sizes = Data::new(self.value_sizes.clone());
+		let avg_size = sizes.iter().sum::<f64>() / sizes.len() as f64;
+
+		println!("\nAvg SET size:\t{} B", fmt::number(avg_size.round() as u64));
+		println!("Min SET size:\t{} B", fmt::number(sizes.min() as u64));
+		println!("p50 SET size:\t{} B", fmt::number(sizes.median() as u64));
+		println!("p99 SET size:\t{} B", fmt::number(sizes.quantile(0.99) as u64));
+		println!("Max SET size:\t{} B", fmt::number(sizes.max() as u64));
+	}
+}
+
+impl HyperLogLog {
+	fn insert(&mut self, key: &str) {
+		let mut hasher = DefaultHasher::new();
+		key.hash(&mut hasher);
+		let hash = hasher.finish();
+
+		let index = (hash >> (64 - HLL_PRECISION)) as usize;
+
+		// the rank is the position of the first set bit in the
+		// remaining bits of the hash
+		let rank = ((hash << HLL_PRECISION).leading_zeros() + 1).min(64 - HLL_PRECISION + 1) as u8;
+
+		self.registers[index] = self.registers[index].max(rank);
+	}
+
+	fn estimate(&self) -> f64 {
+		let count = self.registers.len() as f64;
+		let alpha = 0.7213 / (1.0 + 1.079 / count);
+
+		let sum = self.registers
+			.iter()
+			.map(|register| 2f64.powi(-(*register as i32)))
+			.sum::<f64>();
+
+		let estimate = alpha * count * count / sum;
+
+		let zero_registers = self.registers
+			.iter()
+			.filter(|register| **register == 0)
+			.count();
+
+		// use linear counting for small cardinalities, where the raw
+		// estimate is biased
+		if estimate <= 2.5 * count && zero_registers > 0 {
+			return count * (count / zero_registers as f64).ln();
+		}
+
+		estimate
+	}
+}
+
+impl Default for HyperLogLog {
+	fn default() -> Self {
+		HyperLogLog {
+			registers: vec![0; 1 << HLL_PRECISION],
+		}
+	}
+}
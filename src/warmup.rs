@@ -0,0 +1,174 @@
+/*
+ * Copyright (c) Kia Shakiba
+ *
+ * This source code is licensed under the GNU AGPLv3 license found in the
+ * LICENSE file in the root directory of this source tree.
+ */
+
+use std::{
+	mem,
+	sync::{
+		Mutex,
+		atomic::{AtomicBool, Ordering},
+	},
+	time::Duration,
+};
+
+// the number of warmup requests in each window whose p99 is compared
+const WARMUP_WINDOW: usize = 1000;
+
+/// Collects the latencies of warmup requests from every client and decides
+/// when the p99 latency has stabilized.
+#[derive(Debug)]
+pub struct WarmupMonitor {
+	// the largest percentage change in p99 between consecutive windows
+	// which is considered stable
+	threshold: f64,
+
+	window: Mutex<WarmupWindow>,
+	is_stable: AtomicBool,
+}
+
+#[derive(Debug, Default)]
+struct WarmupWindow {
+	latencies: Vec<f64>,
+	prev_p99: Option<f64>,
+}
+
+impl WarmupMonitor {
+	pub fn new(threshold: f64) -> Self {
+		WarmupMonitor {
+			threshold,
+
+			window: Mutex::new(WarmupWindow::default()),
+			is_stable: AtomicBool::new(false),
+		}
+	}
+
+	pub fn store_latency(&self, latency: Duration) {
+		if self.is_stable() {
+			return;
+		}
+
+		let mut window = self.window
+			.lock()
+			.expect("Could not lock warmup window.");
+
+		window.latencies.push(latency.as_micros() as f64);
+
+		if window.latencies.len() < WARMUP_WINDOW {
+			return;
+		}
+
+		let mut latencies = mem::take(&mut window.latencies);
+		latencies.sort_unstable_by(f64::total_cmp);
+
+		let p99 = latencies[(latencies.len() * 99 / 100).min(latencies.len() - 1)];
+
+		if let Some(prev_p99) = window.prev_p99
+			&& prev_p99 > 0.0
+			&& ((p99 - prev_p99) / prev_p99 * 100.0).abs() <= self.threshold
+		{
+			self.is_stable.store(true, Ordering::Relaxed);
+		}
+
+		window.prev_p99 = Some(p99);
+	}
+
+	/// Discards the latencies stored so far so that the next phase's warmup
+	/// is judged on its own latencies.
+	pub fn reset(&self) {
+		let mut window = self.window
+			.lock()
+			.expect("Could not lock warmup window.");
+
+		*window = WarmupWindow::default();
+		self.is_stable.store(false, Ordering::Relaxed);
+	}
+
+	/// Returns true once the p99 latency of a window of warmup requests is
+	/// within the threshold of the window before it.
+	pub fn is_stable(&self) -> bool {
+		self.is_stable.load(Ordering::Relaxed)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::time::Duration;
+	use crate::warmup::{WarmupMonitor, WARMUP_WINDOW};
+
+	#[test]
+	fn steady_latencies_are_stable_after_two_windows() {
+		let monitor = WarmupMonitor::new(5.0);
+
+		store_window(&monitor, 100);
+		assert!(!monitor.is_stable());
+
+		store_window(&monitor, 102);
+		assert!(monitor.is_stable());
+	}
+
+	#[test]
+	fn changing_latencies_are_not_stable() {
+		let monitor = WarmupMonitor::new(5.0);
+
+		store_window(&monitor, 100);
+		store_window(&monitor, 200);
+		assert!(!monitor.is_stable());
+
+		store_window(&monitor, 150);
+		assert!(!monitor.is_stable());
+
+		store_window(&monitor, 150);
+		assert!(monitor.is_stable());
+	}
+
+	#[test]
+	fn partial_window_is_not_compared() {
+		let monitor = WarmupMonitor::new(5.0);
+
+		store_window(&monitor, 100);
+
+		for _ in 0..WARMUP_WINDOW - 1 {
+			monitor.store_latency(Duration::from_micros(100));
+		}
+
+		assert!(!monitor.is_stable());
+	}
+
+	#[test]
+	fn zero_latencies_are_not_stable() {
+		let monitor = WarmupMonitor::new(5.0);
+
+		store_window(&monitor, 0);
+		store_window(&monitor, 0);
+
+		assert!(!monitor.is_stable());
+	}
+
+	#[test]
+	fn reset_discards_previous_windows() {
+		let monitor = WarmupMonitor::new(5.0);
+
+		store_window(&monitor, 100);
+		store_window(&monitor, 100);
+		assert!(monitor.is_stable());
+
+		monitor.reset();
+		assert!(!monitor.is_stable());
+
+		// the first window after the reset has nothing to be compared to
+		store_window(&monitor, 100);
+		assert!(!monitor.is_stable());
+
+		store_window(&monitor, 100);
+		assert!(monitor.is_stable());
+	}
+
+	fn store_window(monitor: &WarmupMonitor, latency: u64) {
+		for _ in 0..WARMUP_WINDOW {
+			monitor.store_latency(Duration::from_micros(latency));
+		}
+	}
+}